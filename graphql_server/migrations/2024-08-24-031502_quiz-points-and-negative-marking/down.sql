@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE quiz_blocks
+    DROP COLUMN points;
+
+ALTER TABLE assignments
+    DROP COLUMN negative_marking;
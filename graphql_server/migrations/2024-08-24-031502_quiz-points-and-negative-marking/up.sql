@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE quiz_blocks
+    ADD COLUMN points FLOAT NOT NULL DEFAULT 1;
+
+ALTER TABLE assignments
+    ADD COLUMN negative_marking FLOAT;
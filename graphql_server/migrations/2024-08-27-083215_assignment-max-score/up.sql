@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE assignments
+    ADD COLUMN max_score FLOAT;
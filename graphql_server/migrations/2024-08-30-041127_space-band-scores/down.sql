@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE assignments
+    DROP CONSTRAINT assignments_band_score_id_fkey,
+    ADD CONSTRAINT assignments_band_score_id_fkey
+        FOREIGN KEY (band_score_id) REFERENCES band_scores(id);
+
+ALTER TABLE band_scores
+    DROP COLUMN space_id;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+ALTER TABLE band_scores
+    ADD COLUMN space_id INT REFERENCES spaces(id) ON DELETE CASCADE;
+
+ALTER TABLE assignments
+    DROP CONSTRAINT assignments_band_score_id_fkey,
+    ADD CONSTRAINT assignments_band_score_id_fkey
+        FOREIGN KEY (band_score_id) REFERENCES band_scores(id) ON DELETE SET NULL;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE quiz_blocks
+    DROP COLUMN bank_quiz_id;
+
+DROP TABLE bank_quiz_tags;
+DROP TABLE bank_quizzes;
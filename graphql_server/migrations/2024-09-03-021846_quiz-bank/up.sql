@@ -0,0 +1,24 @@
+-- Your SQL goes here
+CREATE TABLE bank_quizzes (
+    id UUID PRIMARY KEY,
+    space_id INT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    creator_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    original_quiz_id UUID REFERENCES quiz_blocks(id) ON DELETE SET NULL,
+    quiz_type INT NOT NULL,
+    question_data JSONB NOT NULL,
+    answer_data JSONB NOT NULL,
+    points FLOAT NOT NULL DEFAULT 1,
+    difficulty INT NOT NULL DEFAULT 0,
+    updated_at BIGINT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE TABLE bank_quiz_tags (
+    bank_quiz_id UUID NOT NULL REFERENCES bank_quizzes(id) ON DELETE CASCADE,
+    tag VARCHAR(256) NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (bank_quiz_id, tag)
+);
+
+ALTER TABLE quiz_blocks
+    ADD COLUMN bank_quiz_id UUID REFERENCES bank_quizzes(id) ON DELETE SET NULL;
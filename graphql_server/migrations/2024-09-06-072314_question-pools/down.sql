@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE assignment_submissions
+    DROP COLUMN random_seed;
+
+ALTER TABLE assignments
+    DROP COLUMN question_pools;
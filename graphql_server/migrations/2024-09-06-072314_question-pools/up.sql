@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE assignments
+    ADD COLUMN question_pools JSONB NOT NULL DEFAULT '{"items": []}';
+
+ALTER TABLE assignment_submissions
+    ADD COLUMN random_seed BIGINT;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE assignments
+    ADD COLUMN shuffle_options BOOL NOT NULL DEFAULT FALSE;
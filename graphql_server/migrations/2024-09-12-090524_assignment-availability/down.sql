@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE assignment_submissions
+    DROP COLUMN is_late;
+
+ALTER TABLE assignments
+    DROP COLUMN available_from,
+    DROP COLUMN due_at,
+    DROP COLUMN close_at,
+    DROP COLUMN late_penalty;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+ALTER TABLE assignments
+    ADD COLUMN available_from BIGINT,
+    ADD COLUMN due_at BIGINT,
+    ADD COLUMN close_at BIGINT,
+    ADD COLUMN late_penalty FLOAT;
+
+ALTER TABLE assignment_submissions
+    ADD COLUMN is_late BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE assignment_accommodations (
+    assignment_id INT NOT NULL REFERENCES assignments(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    test_duration INT,
+    max_number_of_attempt INT,
+    due_at BIGINT,
+    close_at BIGINT,
+    updated_at BIGINT NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (assignment_id, user_id)
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE assignment_submissions
+    DROP COLUMN deadline_at,
+    DROP COLUMN paused_at;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+ALTER TABLE assignment_submissions
+    ADD COLUMN deadline_at BIGINT,
+    ADD COLUMN paused_at BIGINT;
+
+UPDATE assignment_submissions
+SET deadline_at = start_at + test_duration
+WHERE test_duration IS NOT NULL;
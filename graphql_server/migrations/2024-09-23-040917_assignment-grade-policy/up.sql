@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE assignments
+    ADD COLUMN grade_policy INT NOT NULL DEFAULT 0;
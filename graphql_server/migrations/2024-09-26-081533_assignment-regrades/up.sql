@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE assignment_regrades (
+    id UUID PRIMARY KEY,
+    assignment_id INT NOT NULL REFERENCES assignments(id) ON DELETE CASCADE,
+    quiz_id UUID NOT NULL REFERENCES quiz_blocks(id) ON DELETE CASCADE,
+    creator_id INT NOT NULL REFERENCES users(id),
+    status INT NOT NULL DEFAULT 0,
+    summary JSONB NOT NULL DEFAULT '{"number_of_quizzes": 0, "number_of_answers": 0, "changes": []}',
+    updated_at BIGINT NOT NULL,
+    created_at BIGINT NOT NULL
+);
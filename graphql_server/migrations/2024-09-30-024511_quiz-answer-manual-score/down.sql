@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE assignment_submissions DROP COLUMN needs_grading;
+
+ALTER TABLE quiz_user_answer DROP COLUMN graded_at;
+ALTER TABLE quiz_user_answer DROP COLUMN comment;
+ALTER TABLE quiz_user_answer DROP COLUMN manual_score;
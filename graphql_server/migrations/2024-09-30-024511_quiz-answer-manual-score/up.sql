@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE quiz_user_answer ADD COLUMN manual_score FLOAT;
+ALTER TABLE quiz_user_answer ADD COLUMN comment TEXT;
+ALTER TABLE quiz_user_answer ADD COLUMN graded_at BIGINT;
+
+ALTER TABLE assignment_submissions ADD COLUMN needs_grading BOOLEAN NOT NULL DEFAULT FALSE;
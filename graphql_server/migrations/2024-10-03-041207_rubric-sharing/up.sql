@@ -0,0 +1,19 @@
+-- Your SQL goes here
+ALTER TABLE rubrics ADD COLUMN space_id INT REFERENCES spaces(id) ON DELETE CASCADE;
+ALTER TABLE rubrics ADD COLUMN share_mode INT NOT NULL DEFAULT 0;
+ALTER TABLE rubrics ADD COLUMN version INT NOT NULL DEFAULT 1;
+
+CREATE TABLE rubric_versions (
+    rubric_id UUID NOT NULL REFERENCES rubrics(id) ON DELETE CASCADE,
+    version INT NOT NULL,
+    name TEXT NOT NULL,
+    data JSONB NOT NULL,
+    user_id INT NOT NULL REFERENCES users(id),
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (rubric_id, version)
+);
+
+INSERT INTO rubric_versions (rubric_id, version, name, data, user_id, created_at)
+SELECT id, 1, name, data, user_id, updated_at FROM rubrics;
+
+ALTER TABLE rubric_submissions ADD COLUMN rubric_version INT;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE rubric_submissions DROP COLUMN ai_suggested_at;
+ALTER TABLE rubric_submissions DROP COLUMN ai_suggested_data;
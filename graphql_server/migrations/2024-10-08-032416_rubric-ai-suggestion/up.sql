@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE rubric_submissions ADD COLUMN ai_suggested_data JSONB;
+ALTER TABLE rubric_submissions ADD COLUMN ai_suggested_at BIGINT;
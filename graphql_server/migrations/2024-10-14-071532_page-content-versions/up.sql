@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE page_content_versions (
+    id UUID PRIMARY KEY,
+    page_content_id UUID NOT NULL REFERENCES page_contents(id) ON DELETE CASCADE,
+    body JSONB NOT NULL,
+    quiz_data JSONB NOT NULL,
+    name TEXT,
+    is_auto BOOL NOT NULL DEFAULT FALSE,
+    creator_id INT NOT NULL REFERENCES users(id),
+    created_at BIGINT NOT NULL
+);
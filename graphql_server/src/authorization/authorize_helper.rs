@@ -266,9 +266,19 @@ pub async fn rubric_is_allowed(
     action: RubricActionPermission,
 ) -> Result<bool> {
     let oso = ctx.data::<Oso>()?;
-    let user_auth = get_user_auth_by_user_id_from_ctx(ctx, user_id, None).await?;
     let mut conn = get_conn_from_ctx(ctx).await?;
     let rubric = Rubric::find_by_id(&mut conn, rubric_id)?;
+    // Shared rubrics are authorized by the membership of the space owning the rubric
+    let space_member = if let Some(space_id) = rubric.space_id {
+        SpaceMember::find(&mut conn, space_id, user_id).ok()
+    } else {
+        None
+    };
+    let user_auth = if let Some(space_member) = space_member {
+        UserAuth::new(space_member)
+    } else {
+        UserAuth::init_without_space(user_id)
+    };
     let rubric_auth = RubricAuth::new(&rubric);
     let is_allowed = oso.is_allowed(user_auth, action.to_string(), rubric_auth)?;
 
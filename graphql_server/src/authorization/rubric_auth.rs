@@ -10,6 +10,10 @@ pub struct RubricAuth {
     pub id: Uuid,
     #[polar(attribute)]
     pub user_id: i32,
+    #[polar(attribute)]
+    pub space_id: i32,
+    #[polar(attribute)]
+    pub share_mode: String,
 }
 
 impl RubricAuth {
@@ -17,12 +21,18 @@ impl RubricAuth {
         Self {
             id: rubric.id,
             user_id: rubric.user_id,
+            space_id: rubric.space_id.unwrap_or(-1),
+            share_mode: rubric.share_mode.get_name(),
         }
     }
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Enum, Debug, Copy, Clone, Eq, PartialEq, EnumString, Display)]
 #[strum(serialize_all = "snake_case")]
 pub enum RubricActionPermission {
+    ViewRubric,
     ManageRubric,
+    // Removing also removes all versions, so editors of a shared rubric can't do it
+    DeleteRubric,
 }
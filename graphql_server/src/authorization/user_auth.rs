@@ -20,6 +20,15 @@ impl UserAuth {
         }
     }
 
+    // User acting outside of any space, only roles not depending on space are matched
+    pub fn init_without_space(user_id: i32) -> Self {
+        Self {
+            id: user_id,
+            space_id: 0,
+            role: Role::Student,
+        }
+    }
+
     pub fn get_role(&self) -> String {
         match self.role {
             Role::Teacher => "teacher",
@@ -4,11 +4,13 @@ pub mod submission_job;
 use aj::AJ;
 
 use crate::background_job::storage_job::GenerateWaveform;
-use crate::background_job::submission_job::CompleteSubmission;
+use crate::background_job::submission_job::{CloseAssignment, CompleteSubmission, RegradeQuiz};
 
 pub fn register_jobs() {
     let url = std::env::var("REDIS_URL").unwrap();
     let redis = aj::redis::Redis::new(url);
     AJ::register::<CompleteSubmission>("complete_submission", redis.clone());
+    AJ::register::<CloseAssignment>("close_assignment", redis.clone());
+    AJ::register::<RegradeQuiz>("regrade_quiz", redis.clone());
     AJ::register::<GenerateWaveform>("generate_waveform", redis);
 }
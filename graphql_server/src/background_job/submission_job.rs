@@ -1,15 +1,20 @@
 use aj::async_trait::async_trait;
 use aj::Executable;
+use diesel::PgConnection;
 
 use crate::connection_pool::get_conn_from_actor;
-use crate::db::{Assignment, Submission};
+use uuid::Uuid;
+
+use crate::db::{Assignment, AssignmentRegrade, Quiz, RegradeStatus, RegradeSummary, Submission};
 use crate::error::IkigaiError;
-use crate::helper::submit_submission;
+use crate::helper::{get_student_assignment, regrade_quiz, submit_submission};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompleteSubmission {
     pub attempt_number: i32,
     pub submission_id: i32,
+    #[serde(default)]
+    pub deadline_at: Option<i64>,
 }
 
 async fn handle_complete_submission(msg: &CompleteSubmission) -> Result<(), IkigaiError> {
@@ -29,8 +34,23 @@ async fn handle_complete_submission(msg: &CompleteSubmission) -> Result<(), Ikig
         return Ok(());
     }
 
+    // Test is paused, the job is scheduled again when it resumes
+    if submission.paused_at.is_some() {
+        return Ok(());
+    }
+
+    // Deadline is changed, another job is scheduled for the new deadline.
+    // Jobs scheduled before we persist deadline are due at start time + test duration.
+    let scheduled_deadline = msg.deadline_at.or(submission
+        .test_duration
+        .map(|test_duration| submission.start_at + test_duration as i64));
+    if submission.deadline_at.is_some() && submission.deadline_at != scheduled_deadline {
+        return Ok(());
+    }
+
     // Should close the submission
     let assignment = Assignment::find_by_id(&mut conn, submission.assignment_id)?;
+    let assignment = get_student_assignment(&mut conn, assignment, submission.user_id)?;
     submit_submission(&mut conn, &submission, &assignment, true)?;
 
     Ok(())
@@ -49,3 +69,107 @@ impl Executable for CompleteSubmission {
         };
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloseAssignment {
+    pub assignment_id: i32,
+    pub close_at: i64,
+    #[serde(default)]
+    pub user_id: Option<i32>,
+}
+
+async fn handle_close_assignment(msg: &CloseAssignment) -> Result<(), IkigaiError> {
+    info!("Start close assignment by background_job job {:?}", msg);
+    let mut conn = get_conn_from_actor().await?;
+    let assignment = Assignment::find_by_id(&mut conn, msg.assignment_id)?;
+
+    let submissions = Submission::find_all_by_assignment(&mut conn, assignment.id)?;
+    for submission in submissions {
+        if submission.submit_at.is_some() {
+            continue;
+        }
+
+        if msg.user_id.is_some() && msg.user_id != Some(submission.user_id) {
+            continue;
+        }
+
+        // A failing submission must not keep tests of other students open
+        if let Err(e) = close_submission(&mut conn, &assignment, &submission, msg.close_at) {
+            error!(
+                "Cannot close submission {} of assignment {} by {:?}",
+                submission.id, assignment.id, e
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn close_submission(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+    submission: &Submission,
+    close_at: i64,
+) -> Result<(), IkigaiError> {
+    // Close time of the student is changed, another job is scheduled for it
+    let assignment = get_student_assignment(conn, assignment.clone(), submission.user_id)?;
+    if assignment.close_at != Some(close_at) {
+        return Ok(());
+    }
+
+    submit_submission(conn, submission, &assignment, true)
+}
+
+#[async_trait]
+impl Executable for CloseAssignment {
+    type Output = ();
+
+    async fn execute(&self) {
+        if let Err(e) = handle_close_assignment(self).await {
+            error!(
+                "Cannot close assignment {} in background_job job by {:?}",
+                self.assignment_id, e
+            );
+        };
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegradeQuiz {
+    pub regrade_id: Uuid,
+}
+
+async fn handle_regrade_quiz(msg: &RegradeQuiz) -> Result<(), IkigaiError> {
+    info!("Start regrade quiz by background_job job {:?}", msg);
+    let mut conn = get_conn_from_actor().await?;
+    let regrade = AssignmentRegrade::find(&mut conn, msg.regrade_id)?;
+    let assignment = Assignment::find_by_id(&mut conn, regrade.assignment_id)?;
+    let quiz = Quiz::find(&mut conn, regrade.quiz_id)?;
+
+    let summary = regrade_quiz(&mut conn, &assignment, &quiz)?;
+    AssignmentRegrade::update_result(&mut conn, regrade.id, RegradeStatus::Completed, summary)?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl Executable for RegradeQuiz {
+    type Output = ();
+
+    async fn execute(&self) {
+        if let Err(e) = handle_regrade_quiz(self).await {
+            error!(
+                "Cannot regrade quiz of regrade {} in background_job job by {:?}",
+                self.regrade_id, e
+            );
+            if let Ok(mut conn) = get_conn_from_actor().await {
+                let _ = AssignmentRegrade::update_result(
+                    &mut conn,
+                    self.regrade_id,
+                    RegradeStatus::Failed,
+                    RegradeSummary::default(),
+                );
+            }
+        };
+    }
+}
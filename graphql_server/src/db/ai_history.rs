@@ -14,6 +14,7 @@ use crate::util::get_now_as_secs;
 #[diesel(sql_type = Integer)]
 pub enum AIAction {
     GenerateQuizzes,
+    GradeByRubric,
 }
 
 impl_enum_for_db!(AIAction);
@@ -1,11 +1,17 @@
 use diesel::result::Error;
-use diesel::sql_types::Integer;
+use diesel::sql_types::{Integer, Jsonb};
 use diesel::{AsChangeset, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use super::schema::assignments;
-use crate::impl_enum_for_db;
+use super::{Quiz, Submission};
 use crate::util::get_now_as_secs;
+use crate::{impl_enum_for_db, impl_jsonb_for_db};
 
 #[derive(
     Debug, Clone, Copy, Eq, PartialEq, FromPrimitive, ToPrimitive, AsExpression, FromSqlRow, Enum,
@@ -25,6 +31,54 @@ impl Default for GradeMethod {
     }
 }
 
+// How the grade of a student is resolved from their attempts
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, FromPrimitive, ToPrimitive, AsExpression, FromSqlRow, Enum,
+)]
+#[diesel(sql_type = Integer)]
+pub enum GradePolicy {
+    Latest,
+    Highest,
+    First,
+    Average,
+}
+
+impl_enum_for_db!(GradePolicy);
+
+impl Default for GradePolicy {
+    fn default() -> Self {
+        Self::Latest
+    }
+}
+
+impl GradePolicy {
+    // Returns the grade and the submission deciding it, average grade doesn't belong to any submission
+    pub fn resolve(&self, submissions: &[Submission]) -> Option<(f64, Option<i32>)> {
+        let graded_submissions = submissions
+            .iter()
+            .filter_map(|submission| submission.final_grade.map(|grade| (grade, submission)))
+            .sorted_by_key(|(_, submission)| submission.attempt_number)
+            .collect_vec();
+        if graded_submissions.is_empty() {
+            return None;
+        }
+
+        let (grade, submission) = match self {
+            GradePolicy::Latest => graded_submissions.last()?,
+            GradePolicy::First => graded_submissions.first()?,
+            GradePolicy::Highest => graded_submissions
+                .iter()
+                .max_by(|(a, _), (b, _)| a.total_cmp(b))?,
+            GradePolicy::Average => {
+                let total: f64 = graded_submissions.iter().map(|(grade, _)| grade).sum();
+                return Some((total / graded_submissions.len() as f64, None));
+            }
+        };
+
+        Some((*grade, Some(submission.id)))
+    }
+}
+
 #[derive(Debug, Clone, Insertable, Default)]
 #[diesel(table_name = assignments)]
 pub struct NewAssignment {
@@ -37,6 +91,15 @@ pub struct NewAssignment {
     pub band_score_id: Option<i32>,
     pub grade_method: GradeMethod,
     pub grade_by_rubric_id: Option<Uuid>,
+    pub negative_marking: Option<f64>,
+    pub max_score: Option<f64>,
+    pub question_pools: QuestionPools,
+    pub shuffle_options: bool,
+    pub available_from: Option<i64>,
+    pub due_at: Option<i64>,
+    pub close_at: Option<i64>,
+    pub late_penalty: Option<f64>,
+    pub grade_policy: GradePolicy,
 }
 
 impl From<Assignment> for NewAssignment {
@@ -51,6 +114,15 @@ impl From<Assignment> for NewAssignment {
             band_score_id: assignment.band_score_id,
             grade_method: assignment.grade_method,
             grade_by_rubric_id: assignment.grade_by_rubric_id,
+            negative_marking: assignment.negative_marking,
+            max_score: assignment.max_score,
+            question_pools: assignment.question_pools,
+            shuffle_options: assignment.shuffle_options,
+            available_from: assignment.available_from,
+            due_at: assignment.due_at,
+            close_at: assignment.close_at,
+            late_penalty: assignment.late_penalty,
+            grade_policy: assignment.grade_policy,
         }
     }
 }
@@ -78,6 +150,15 @@ pub struct UpdateAssignmentData {
     pub band_score_id: Option<i32>,
     pub grade_method: GradeMethod,
     pub grade_by_rubric_id: Option<Uuid>,
+    pub negative_marking: Option<f64>,
+    #[graphql(default)]
+    pub shuffle_options: bool,
+    pub available_from: Option<i64>,
+    pub due_at: Option<i64>,
+    pub close_at: Option<i64>,
+    pub late_penalty: Option<f64>,
+    #[graphql(default)]
+    pub grade_policy: GradePolicy,
     #[graphql(skip)]
     pub updated_at: i64,
 }
@@ -95,9 +176,34 @@ pub struct Assignment {
     pub band_score_id: Option<i32>,
     pub grade_method: GradeMethod,
     pub grade_by_rubric_id: Option<Uuid>,
+    pub negative_marking: Option<f64>,
+    #[graphql(skip)]
+    pub max_score: Option<f64>,
+    pub question_pools: QuestionPools,
+    pub shuffle_options: bool,
+    pub available_from: Option<i64>,
+    pub due_at: Option<i64>,
+    // Students cannot start or submit after this time, in-progress submissions are auto submitted
+    pub close_at: Option<i64>,
+    // Fraction of final grade deducted from submissions submitted after due time
+    pub late_penalty: Option<f64>,
+    pub grade_policy: GradePolicy,
 }
 
 impl Assignment {
+    pub fn is_available(&self, now: i64) -> bool {
+        self.available_from
+            .map_or(true, |available_from| available_from <= now)
+    }
+
+    pub fn is_closed(&self, now: i64) -> bool {
+        self.close_at.map_or(false, |close_at| close_at <= now)
+    }
+
+    pub fn is_late(&self, now: i64) -> bool {
+        self.due_at.map_or(false, |due_at| due_at < now)
+    }
+
     pub fn insert(
         conn: &mut PgConnection,
         mut new_assignment: NewAssignment,
@@ -128,6 +234,32 @@ impl Assignment {
             .get_result(conn)
     }
 
+    pub fn update_max_score(
+        conn: &mut PgConnection,
+        assignment_id: i32,
+        max_score: f64,
+    ) -> Result<Self, Error> {
+        diesel::update(assignments::table.find(assignment_id))
+            .set((
+                assignments::max_score.eq(max_score),
+                assignments::updated_at.eq(get_now_as_secs()),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn update_question_pools(
+        conn: &mut PgConnection,
+        assignment_id: i32,
+        question_pools: &QuestionPools,
+    ) -> Result<Self, Error> {
+        diesel::update(assignments::table.find(assignment_id))
+            .set((
+                assignments::question_pools.eq(question_pools),
+                assignments::updated_at.eq(get_now_as_secs()),
+            ))
+            .get_result(conn)
+    }
+
     pub fn find_by_id(conn: &mut PgConnection, assignment_id: i32) -> Result<Self, Error> {
         assignments::table.find(assignment_id).first(conn)
     }
@@ -164,3 +296,94 @@ impl Assignment {
             .get_results(conn)
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, InputObject)]
+#[graphql(input_name = "QuestionPoolInput")]
+pub struct QuestionPool {
+    pub quiz_ids: Vec<Uuid>,
+    // Number of quizzes drawn from this pool for each submission
+    pub draw_count: i32,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    Serialize,
+    Deserialize,
+    SimpleObject,
+    InputObject,
+    AsExpression,
+    FromSqlRow,
+)]
+#[graphql(input_name = "QuestionPoolsInput")]
+#[diesel(sql_type = Jsonb)]
+pub struct QuestionPools {
+    pub items: Vec<QuestionPool>,
+}
+
+impl_jsonb_for_db!(QuestionPools);
+
+impl QuestionPools {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // Quizzes of the pools which are not drawn with this seed
+    pub fn excluded_quiz_ids(&self, seed: i64) -> Vec<Uuid> {
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        let mut res = vec![];
+        for pool in self.items.iter() {
+            let mut quiz_ids = pool.quiz_ids.clone();
+            quiz_ids.shuffle(&mut rng);
+            let draw_count = pool.draw_count.max(0) as usize;
+            res.extend(quiz_ids.into_iter().skip(draw_count));
+        }
+
+        res
+    }
+
+    // Quizzes outside of pools are always presented, each pool contributes `draw_count` quizzes.
+    // Pool quizzes are expected to have the same points, so we use the average one.
+    pub fn max_score(&self, quizzes: &[Quiz]) -> f64 {
+        let points_by_quiz: HashMap<Uuid, f64> =
+            quizzes.iter().map(|quiz| (quiz.id, quiz.points)).collect();
+        let mut max_score: f64 = quizzes.iter().map(|quiz| quiz.points).sum();
+        for pool in self.items.iter() {
+            let pool_points: Vec<f64> = pool
+                .quiz_ids
+                .iter()
+                .filter_map(|quiz_id| points_by_quiz.get(quiz_id))
+                .copied()
+                .collect();
+            if pool_points.is_empty() {
+                continue;
+            }
+
+            let total_pool_points: f64 = pool_points.iter().sum();
+            let draw_count = (pool.draw_count.max(0) as usize).min(pool_points.len());
+            max_score -= total_pool_points;
+            max_score += total_pool_points / pool_points.len() as f64 * draw_count as f64;
+        }
+
+        max_score
+    }
+
+    // Pools of a cloned assignment point to the cloned quizzes
+    pub fn replace_quiz_ids(&self, new_quiz_ids: &HashMap<Uuid, Uuid>) -> Self {
+        let items = self
+            .items
+            .iter()
+            .map(|pool| QuestionPool {
+                quiz_ids: pool
+                    .quiz_ids
+                    .iter()
+                    .filter_map(|quiz_id| new_quiz_ids.get(quiz_id))
+                    .copied()
+                    .collect(),
+                draw_count: pool.draw_count,
+            })
+            .collect();
+        Self { items }
+    }
+}
@@ -0,0 +1,96 @@
+use diesel::result::Error;
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+
+use super::schema::assignment_accommodations;
+use super::Assignment;
+use crate::util::get_now_as_secs;
+
+// Overrides of assignment settings for a student, e.g. extra time or extra attempts
+#[derive(Debug, Clone, Insertable, Queryable, SimpleObject, InputObject)]
+#[graphql(input_name = "AssignmentAccommodationInput")]
+#[diesel(table_name = assignment_accommodations)]
+pub struct AssignmentAccommodation {
+    pub assignment_id: i32,
+    pub user_id: i32,
+    pub test_duration: Option<i32>,
+    pub max_number_of_attempt: Option<i32>,
+    pub due_at: Option<i64>,
+    pub close_at: Option<i64>,
+    #[graphql(skip_input)]
+    pub updated_at: i64,
+    #[graphql(skip_input)]
+    pub created_at: i64,
+}
+
+impl AssignmentAccommodation {
+    pub fn upsert(conn: &mut PgConnection, mut item: Self) -> Result<Self, Error> {
+        item.updated_at = get_now_as_secs();
+        item.created_at = get_now_as_secs();
+
+        diesel::insert_into(assignment_accommodations::table)
+            .values(&item)
+            .on_conflict((
+                assignment_accommodations::assignment_id,
+                assignment_accommodations::user_id,
+            ))
+            .do_update()
+            .set((
+                assignment_accommodations::test_duration.eq(&item.test_duration),
+                assignment_accommodations::max_number_of_attempt.eq(&item.max_number_of_attempt),
+                assignment_accommodations::due_at.eq(&item.due_at),
+                assignment_accommodations::close_at.eq(&item.close_at),
+                assignment_accommodations::updated_at.eq(&item.updated_at),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn find_opt(
+        conn: &mut PgConnection,
+        assignment_id: i32,
+        user_id: i32,
+    ) -> Result<Option<Self>, Error> {
+        match assignment_accommodations::table
+            .find((assignment_id, user_id))
+            .first(conn)
+        {
+            Ok(item) => Ok(Some(item)),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn find_all_by_assignment(
+        conn: &mut PgConnection,
+        assignment_id: i32,
+    ) -> Result<Vec<Self>, Error> {
+        assignment_accommodations::table
+            .filter(assignment_accommodations::assignment_id.eq(assignment_id))
+            .get_results(conn)
+    }
+
+    pub fn remove(conn: &mut PgConnection, assignment_id: i32, user_id: i32) -> Result<(), Error> {
+        diesel::delete(assignment_accommodations::table.find((assignment_id, user_id)))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+impl Assignment {
+    // Settings of the assignment which apply to the student of the accommodation
+    pub fn with_accommodation(mut self, accommodation: &AssignmentAccommodation) -> Self {
+        if accommodation.test_duration.is_some() {
+            self.test_duration = accommodation.test_duration;
+        }
+        if accommodation.max_number_of_attempt.is_some() {
+            self.max_number_of_attempt = accommodation.max_number_of_attempt;
+        }
+        if accommodation.due_at.is_some() {
+            self.due_at = accommodation.due_at;
+        }
+        if accommodation.close_at.is_some() {
+            self.close_at = accommodation.close_at;
+        }
+
+        self
+    }
+}
@@ -0,0 +1,125 @@
+use diesel::result::Error;
+use diesel::sql_types::{Integer, Jsonb};
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use super::schema::assignment_regrades;
+use crate::util::get_now_as_secs;
+use crate::{impl_enum_for_db, impl_jsonb_for_db};
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    FromPrimitive,
+    ToPrimitive,
+    AsExpression,
+    FromSqlRow,
+    Enum,
+    Serialize,
+    Deserialize,
+)]
+#[diesel(sql_type = Integer)]
+pub enum RegradeStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+impl_enum_for_db!(RegradeStatus);
+
+impl Default for RegradeStatus {
+    fn default() -> Self {
+        Self::Pending
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct RegradeGradeChange {
+    pub submission_id: i32,
+    pub user_id: i32,
+    pub old_auto_grade: Option<f64>,
+    pub new_auto_grade: f64,
+    pub old_final_grade: Option<f64>,
+    pub new_final_grade: Option<f64>,
+    // Final grade given by teacher is kept, only auto grade is updated
+    pub is_final_grade_kept: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, SimpleObject, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Jsonb)]
+pub struct RegradeSummary {
+    // Copies of the quiz receiving the corrected answer key
+    pub number_of_quizzes: i32,
+    pub number_of_answers: i32,
+    // Only submissions having a different grade
+    pub changes: Vec<RegradeGradeChange>,
+}
+
+impl_jsonb_for_db!(RegradeSummary);
+
+// Audit of re-grading submissions after the answer key of an assignment quiz is changed
+#[derive(Debug, Clone, Insertable, Queryable, SimpleObject)]
+#[diesel(table_name = assignment_regrades)]
+pub struct AssignmentRegrade {
+    pub id: Uuid,
+    pub assignment_id: i32,
+    pub quiz_id: Uuid,
+    pub creator_id: i32,
+    pub status: RegradeStatus,
+    pub summary: RegradeSummary,
+    pub updated_at: i64,
+    pub created_at: i64,
+}
+
+impl AssignmentRegrade {
+    pub fn new(assignment_id: i32, quiz_id: Uuid, creator_id: i32) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            assignment_id,
+            quiz_id,
+            creator_id,
+            status: RegradeStatus::Pending,
+            summary: RegradeSummary::default(),
+            updated_at: get_now_as_secs(),
+            created_at: get_now_as_secs(),
+        }
+    }
+
+    pub fn insert(conn: &mut PgConnection, item: Self) -> Result<Self, Error> {
+        diesel::insert_into(assignment_regrades::table)
+            .values(item)
+            .get_result(conn)
+    }
+
+    pub fn update_result(
+        conn: &mut PgConnection,
+        id: Uuid,
+        status: RegradeStatus,
+        summary: RegradeSummary,
+    ) -> Result<Self, Error> {
+        diesel::update(assignment_regrades::table.find(id))
+            .set((
+                assignment_regrades::status.eq(status),
+                assignment_regrades::summary.eq(summary),
+                assignment_regrades::updated_at.eq(get_now_as_secs()),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn find(conn: &mut PgConnection, id: Uuid) -> Result<Self, Error> {
+        assignment_regrades::table.find(id).first(conn)
+    }
+
+    pub fn find_all_by_assignment(
+        conn: &mut PgConnection,
+        assignment_id: i32,
+    ) -> Result<Vec<Self>, Error> {
+        assignment_regrades::table
+            .filter(assignment_regrades::assignment_id.eq(assignment_id))
+            .order_by(assignment_regrades::created_at.desc())
+            .get_results(conn)
+    }
+}
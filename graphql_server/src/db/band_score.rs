@@ -1,9 +1,11 @@
 use diesel::result::Error;
 use diesel::sql_types::Jsonb;
-use diesel::{PgConnection, QueryDsl, RunQueryDsl};
+use diesel::{BoolExpressionMethods, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
 
 use super::schema::band_scores;
+use crate::graphql::validator::BandScoreRangesValidator;
 use crate::impl_jsonb_for_db;
+use crate::util::get_now_as_secs;
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, InputObject)]
 #[graphql(input_name = "BandScoreRangeInput")]
@@ -19,6 +21,20 @@ impl BandScoreRange {
     }
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Enum)]
+pub enum BandScoreRangeType {
+    // Ranges are raw grade, e.g. 30 -> 32 correct answers
+    RawScore,
+    // Ranges are percentage (0 -> 100) of the assignment max score
+    Percentage,
+}
+
+impl Default for BandScoreRangeType {
+    fn default() -> Self {
+        Self::RawScore
+    }
+}
+
 #[derive(
     Debug,
     Clone,
@@ -34,6 +50,9 @@ impl BandScoreRange {
 #[diesel(sql_type = Jsonb)]
 pub struct BandScoreRanges {
     pub items: Vec<BandScoreRange>,
+    #[serde(default)]
+    #[graphql(default)]
+    pub range_type: BandScoreRangeType,
 }
 
 impl BandScoreRanges {
@@ -51,7 +70,10 @@ impl BandScoreRanges {
             BandScoreRange::new(37, 38, 8.5),
             BandScoreRange::new(39, 40, 9.0),
         ];
-        Self { items }
+        Self {
+            items,
+            range_type: BandScoreRangeType::RawScore,
+        }
     }
 
     pub fn init_ielts_reading_academic() -> Self {
@@ -70,7 +92,10 @@ impl BandScoreRanges {
             BandScoreRange::new(37, 38, 8.5),
             BandScoreRange::new(39, 40, 9.0),
         ];
-        Self { items }
+        Self {
+            items,
+            range_type: BandScoreRangeType::RawScore,
+        }
     }
 
     pub fn init_ielts_reading_general() -> Self {
@@ -89,7 +114,50 @@ impl BandScoreRanges {
             BandScoreRange::new(39, 39, 8.5),
             BandScoreRange::new(40, 40, 9.0),
         ];
-        Self { items }
+        Self {
+            items,
+            range_type: BandScoreRangeType::RawScore,
+        }
+    }
+}
+
+impl BandScoreRanges {
+    // Highest band score of the ranges
+    pub fn max_score(&self) -> Option<f64> {
+        self.items
+            .iter()
+            .map(|item| item.score)
+            .max_by(|a, b| a.total_cmp(b))
+    }
+
+    pub fn find_score(&self, grade: f64, max_score: f64) -> f64 {
+        let value = match self.range_type {
+            BandScoreRangeType::RawScore => grade,
+            BandScoreRangeType::Percentage => {
+                if max_score > 0.0 {
+                    grade / max_score * 100.0
+                } else {
+                    0.0
+                }
+            }
+        };
+        // Ranges are inclusive integers, so a fractional score (e.g. 12.5)
+        // belongs to the range of its integer part. If ranges overlap,
+        // the range starting higher wins. A value in a gap between ranges or above
+        // all ranges gets the score of the range below it, a value below the lowest
+        // range (e.g. 0 correct answers of IELTS presets) gets 0.
+        let item = self
+            .items
+            .iter()
+            .filter(|item| item.from as f64 <= value && value < item.to as f64 + 1.0)
+            .max_by_key(|item| item.from)
+            .or_else(|| {
+                self.items
+                    .iter()
+                    .filter(|item| (item.to as f64) < value)
+                    .max_by_key(|item| item.to)
+            });
+        item.map_or(0.0, |range| range.score)
     }
 }
 
@@ -99,12 +167,19 @@ impl_jsonb_for_db!(BandScoreRanges);
 #[diesel(table_name = band_scores)]
 pub struct NewBandScore {
     pub name: String,
+    #[graphql(validator(custom = "BandScoreRangesValidator"))]
     pub range: BandScoreRanges,
+    #[graphql(skip)]
+    pub space_id: Option<i32>,
 }
 
 impl NewBandScore {
     pub fn new(name: String, range: BandScoreRanges) -> Self {
-        Self { name, range }
+        Self {
+            name,
+            range,
+            space_id: None,
+        }
     }
 }
 
@@ -115,16 +190,38 @@ pub struct BandScore {
     pub range: BandScoreRanges,
     pub updated_at: i64,
     pub created_at: i64,
+    pub space_id: Option<i32>,
 }
 
 impl BandScore {
-    pub fn find_score(&self, grade: f64) -> f64 {
-        let item = self
-            .range
-            .items
-            .iter()
-            .find(|item| item.from as f64 <= grade && grade <= item.to as f64);
-        item.map_or(grade, |range| range.score)
+    pub fn find_score(&self, grade: f64, max_score: f64) -> f64 {
+        self.range.find_score(grade, max_score)
+    }
+
+    pub fn insert(conn: &mut PgConnection, item: NewBandScore) -> Result<Self, Error> {
+        diesel::insert_into(band_scores::table)
+            .values(item)
+            .get_result(conn)
+    }
+
+    pub fn update(
+        conn: &mut PgConnection,
+        band_score_id: i32,
+        name: String,
+        range: BandScoreRanges,
+    ) -> Result<Self, Error> {
+        diesel::update(band_scores::table.find(band_score_id))
+            .set((
+                band_scores::name.eq(name),
+                band_scores::range.eq(range),
+                band_scores::updated_at.eq(get_now_as_secs()),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn remove(conn: &mut PgConnection, band_score_id: i32) -> Result<(), Error> {
+        diesel::delete(band_scores::table.find(band_score_id)).execute(conn)?;
+        Ok(())
     }
 
     pub fn find(conn: &mut PgConnection, band_score_id: i32) -> Result<Self, Error> {
@@ -134,4 +231,81 @@ impl BandScore {
     pub fn find_all(conn: &mut PgConnection) -> Result<Vec<Self>, Error> {
         band_scores::table.get_results(conn)
     }
+
+    // Global presets and band scores owned by the space
+    pub fn find_all_by_space(conn: &mut PgConnection, space_id: i32) -> Result<Vec<Self>, Error> {
+        band_scores::table
+            .filter(
+                band_scores::space_id
+                    .is_null()
+                    .or(band_scores::space_id.eq(space_id)),
+            )
+            .get_results(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+
+    use super::*;
+
+    #[test]
+    fn find_score_in_raw_score_ranges() {
+        let ranges = BandScoreRanges::init_ielts_listening();
+        assert_eq!(ranges.find_score(11.0, 40.0), 4.0);
+        assert_eq!(ranges.find_score(12.5, 40.0), 4.0);
+        assert_eq!(ranges.find_score(13.0, 40.0), 4.5);
+        assert_eq!(ranges.find_score(40.0, 40.0), 9.0);
+    }
+
+    #[test]
+    fn find_score_outside_ranges() {
+        let ranges = BandScoreRanges::init_ielts_listening();
+        assert_eq!(ranges.find_score(0.0, 40.0), 0.0);
+        assert_eq!(ranges.find_score(10.5, 40.0), 0.0);
+        assert_eq!(ranges.find_score(45.0, 40.0), 9.0);
+
+        let with_gap = BandScoreRanges {
+            items: vec![
+                BandScoreRange::new(0, 4, 1.0),
+                BandScoreRange::new(10, 20, 2.0),
+            ],
+            range_type: BandScoreRangeType::RawScore,
+        };
+        assert_eq!(with_gap.find_score(5.0, 20.0), 1.0);
+        assert_eq!(with_gap.find_score(9.0, 20.0), 1.0);
+        assert_eq!(with_gap.find_score(10.0, 20.0), 2.0);
+    }
+
+    #[test]
+    fn find_score_in_percentage_ranges() {
+        let ranges = BandScoreRanges {
+            items: vec![
+                BandScoreRange::new(50, 79, 5.0),
+                BandScoreRange::new(80, 100, 8.0),
+            ],
+            range_type: BandScoreRangeType::Percentage,
+        };
+        assert_eq!(ranges.find_score(9.0, 10.0), 8.0);
+        assert_eq!(ranges.find_score(6.0, 10.0), 5.0);
+        // 45% is below the lowest range, it never becomes a band score of 45
+        assert_eq!(ranges.find_score(4.5, 10.0), 0.0);
+        assert_eq!(ranges.find_score(4.5, 0.0), 0.0);
+    }
+
+    #[test]
+    fn find_score_without_ranges() {
+        assert_eq!(BandScoreRanges::default().find_score(30.0, 40.0), 0.0);
+    }
+
+    #[test]
+    fn max_score_of_ranges() {
+        assert_eq!(
+            BandScoreRanges::init_ielts_listening().max_score(),
+            Some(9.0)
+        );
+        assert_eq!(BandScoreRanges::default().max_score(), None);
+    }
 }
@@ -1,12 +1,16 @@
 pub mod ai_history;
 pub mod assignment;
+pub mod assignment_accommodation;
+pub mod assignment_regrade;
 pub mod band_score;
 pub mod document;
 pub mod embedded_session;
 pub mod file;
 pub mod notification;
 pub mod page;
+pub mod page_content_version;
 pub mod quiz;
+pub mod quiz_bank;
 pub mod rubric;
 pub mod schema;
 pub mod space;
@@ -17,13 +21,17 @@ pub mod user;
 
 pub use ai_history::*;
 pub use assignment::*;
+pub use assignment_accommodation::*;
+pub use assignment_regrade::*;
 pub use band_score::*;
 pub use document::*;
 pub use embedded_session::*;
 pub use file::*;
 pub use notification::*;
 pub use page::*;
+pub use page_content_version::*;
 pub use quiz::*;
+pub use quiz_bank::*;
 pub use rubric::*;
 pub use space::*;
 pub use space_member::*;
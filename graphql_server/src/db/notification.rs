@@ -77,6 +77,17 @@ impl Notification {
             .execute(conn)?;
         Ok(notification)
     }
+
+    pub fn insert_many(
+        conn: &mut PgConnection,
+        notifications: Vec<Self>,
+    ) -> Result<Vec<Self>, Error> {
+        diesel::insert_into(notifications::table)
+            .values(&notifications)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        Ok(notifications)
+    }
 }
 
 pub trait ContextMessage {
@@ -1,6 +1,6 @@
 use diesel::result::Error;
 use diesel::sql_types::Integer;
-use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use diesel::{ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl};
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -148,6 +148,26 @@ impl PageContent {
         page_contents::table.find(id).first(conn)
     }
 
+    // Update the body only if it is not changed since it was read, returns None otherwise
+    pub fn update_body_if_unchanged(
+        conn: &mut PgConnection,
+        id: Uuid,
+        old_body: &serde_json::Value,
+        body: &serde_json::Value,
+    ) -> Result<Option<Self>, Error> {
+        diesel::update(
+            page_contents::table
+                .find(id)
+                .filter(page_contents::body.eq(old_body)),
+        )
+        .set((
+            page_contents::body.eq(body),
+            page_contents::updated_at.eq(get_now_as_secs()),
+        ))
+        .get_result(conn)
+        .optional()
+    }
+
     pub fn find_all_by_page(conn: &mut PgConnection, page_id: Uuid) -> Result<Vec<Self>, Error> {
         page_contents::table
             .filter(page_contents::page_id.eq(page_id))
@@ -232,6 +252,31 @@ impl JSONContent {
         }
     }
 
+    pub fn remove_quiz_blocks(&mut self, quiz_ids: &[Uuid]) {
+        if quiz_ids.is_empty() {
+            return;
+        }
+
+        if let Some(contents) = self.content.as_mut() {
+            contents.retain(|content| {
+                let is_quiz_block = content.content_type.as_ref().map_or(false, |content_type| {
+                    ALL_QUIZ_TYPES
+                        .iter()
+                        .any(|quiz| quiz.block_name() == content_type.as_str())
+                });
+                let quiz_id = content
+                    .attrs
+                    .as_ref()
+                    .and_then(|attrs| attrs.get("quizId"))
+                    .and_then(|quiz_id| serde_json::from_value::<Uuid>(quiz_id.clone()).ok());
+                !(is_quiz_block && quiz_id.map_or(false, |quiz_id| quiz_ids.contains(&quiz_id)))
+            });
+            for content in contents {
+                content.remove_quiz_blocks(quiz_ids);
+            }
+        }
+    }
+
     pub fn has_file_handler(&self, file_id: Uuid) -> bool {
         let file_value = serde_json::to_value(file_id).unwrap_or_default();
         let predicate =
@@ -0,0 +1,114 @@
+use diesel::result::Error;
+use diesel::sql_types::Jsonb;
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::schema::page_content_versions;
+use super::{PageContent, Quiz, QuizType};
+use crate::impl_jsonb_for_db;
+use crate::util::get_now_as_secs;
+
+// Content of a quiz block placed in the page content at the time of the version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionQuiz {
+    pub id: Uuid,
+    pub quiz_type: QuizType,
+    pub question_data: Value,
+    pub answer_data: Value,
+    pub points: f64,
+}
+
+impl From<&Quiz> for VersionQuiz {
+    fn from(quiz: &Quiz) -> Self {
+        Self {
+            id: quiz.id,
+            quiz_type: quiz.quiz_type,
+            question_data: quiz.question_data.clone(),
+            answer_data: quiz.answer_data.clone(),
+            points: quiz.points,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Jsonb)]
+pub struct VersionQuizData {
+    pub quizzes: Vec<VersionQuiz>,
+}
+
+impl_jsonb_for_db!(VersionQuizData);
+
+#[derive(Debug, Clone, Insertable, Queryable, SimpleObject)]
+#[graphql(complex)]
+#[diesel(table_name = page_content_versions)]
+pub struct PageContentVersion {
+    pub id: Uuid,
+    pub page_content_id: Uuid,
+    pub body: Value,
+    #[graphql(skip)]
+    pub quiz_data: VersionQuizData,
+    pub name: Option<String>,
+    // Created automatically while editing the page content
+    pub is_auto: bool,
+    pub creator_id: i32,
+    pub created_at: i64,
+}
+
+impl PageContentVersion {
+    pub fn new(
+        page_content: &PageContent,
+        quizzes: &[Quiz],
+        name: Option<String>,
+        is_auto: bool,
+        creator_id: i32,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            page_content_id: page_content.id,
+            body: page_content.body.clone(),
+            quiz_data: VersionQuizData {
+                quizzes: quizzes.iter().map(VersionQuiz::from).collect(),
+            },
+            name,
+            is_auto,
+            creator_id,
+            created_at: get_now_as_secs(),
+        }
+    }
+
+    pub fn insert(conn: &mut PgConnection, item: Self) -> Result<Self, Error> {
+        diesel::insert_into(page_content_versions::table)
+            .values(item)
+            .get_result(conn)
+    }
+
+    pub fn find(conn: &mut PgConnection, id: Uuid) -> Result<Self, Error> {
+        page_content_versions::table.find(id).first(conn)
+    }
+
+    pub fn find_latest(
+        conn: &mut PgConnection,
+        page_content_id: Uuid,
+    ) -> Result<Option<Self>, Error> {
+        let res = page_content_versions::table
+            .filter(page_content_versions::page_content_id.eq(page_content_id))
+            .order_by(page_content_versions::created_at.desc())
+            .first(conn);
+        match res {
+            Ok(item) => Ok(Some(item)),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn find_all_by_page_content(
+        conn: &mut PgConnection,
+        page_content_id: Uuid,
+    ) -> Result<Vec<Self>, Error> {
+        page_content_versions::table
+            .filter(page_content_versions::page_content_id.eq(page_content_id))
+            .order_by(page_content_versions::created_at.desc())
+            .get_results(conn)
+    }
+}
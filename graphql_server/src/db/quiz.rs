@@ -1,13 +1,19 @@
 use diesel::result::Error;
 use diesel::sql_types::Integer;
 use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use regex::RegexBuilder;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
 use std::clone::Clone;
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
-use super::schema::{quiz_blocks, quiz_user_answer};
+use super::schema::{page_contents, pages, quiz_blocks, quiz_user_answer};
 use crate::impl_enum_for_db;
 use crate::util::get_now_as_secs;
 
@@ -33,6 +39,9 @@ pub enum QuizType {
     SelectOption,
     SingleChoice,
     MultipleChoice,
+    Ordering,
+    Matching,
+    Numeric,
 }
 
 impl_enum_for_db!(QuizType);
@@ -45,6 +54,9 @@ impl QuizType {
             QuizType::SelectOption => "selectOption",
             QuizType::SingleChoice => "singleChoice",
             QuizType::MultipleChoice => "multipleChoice",
+            QuizType::Ordering => "ordering",
+            QuizType::Matching => "matching",
+            QuizType::Numeric => "numeric",
         }
     }
 
@@ -53,12 +65,15 @@ impl QuizType {
     }
 }
 
-pub const ALL_QUIZ_TYPES: [QuizType; 5] = [
+pub const ALL_QUIZ_TYPES: [QuizType; 8] = [
     QuizType::WritingBlock,
     QuizType::FillInBlank,
     QuizType::SelectOption,
     QuizType::SingleChoice,
     QuizType::MultipleChoice,
+    QuizType::Ordering,
+    QuizType::Matching,
+    QuizType::Numeric,
 ];
 
 #[derive(Debug, Clone, Insertable, Queryable, SimpleObject, InputObject, Builder)]
@@ -85,6 +100,16 @@ pub struct Quiz {
     #[graphql(skip_input)]
     #[builder(default = "get_now_as_secs()")]
     pub created_at: i64,
+    // Set by `points` argument of quiz upsert, so it's not reset when the argument is missing
+    #[graphql(skip_input)]
+    #[builder(default = "1.0")]
+    pub points: f64,
+    // Bank entry this quiz is inserted from, used to propagate edits of the entry.
+    // Provenance of bank copies is kept here instead of `original_quiz_id`, which links
+    // quizzes of a submission to the assignment quizzes they are graded against.
+    #[graphql(skip_input)]
+    #[builder(default)]
+    pub bank_quiz_id: Option<Uuid>,
 }
 
 impl Quiz {
@@ -104,6 +129,8 @@ impl Quiz {
                 quiz_blocks::question_data.eq(&item.question_data),
                 quiz_blocks::answer_data.eq(&item.answer_data),
                 quiz_blocks::updated_at.eq(&item.updated_at),
+                quiz_blocks::points.eq(&item.points),
+                quiz_blocks::bank_quiz_id.eq(&item.bank_quiz_id),
             ))
             .get_result(conn)
     }
@@ -133,6 +160,108 @@ impl Quiz {
             .filter(quiz_blocks::page_content_id.eq_any(page_content_ids))
             .get_results(conn)
     }
+
+    // Copies of a bank quiz along with the document they are placed in
+    pub fn find_all_by_bank_quiz(
+        conn: &mut PgConnection,
+        bank_quiz_id: Uuid,
+    ) -> Result<Vec<(Self, Uuid)>, Error> {
+        quiz_blocks::table
+            .inner_join(page_contents::table.inner_join(pages::table))
+            .filter(quiz_blocks::bank_quiz_id.eq(bank_quiz_id))
+            .select((quiz_blocks::all_columns, pages::document_id))
+            .get_results(conn)
+    }
+
+    // Copies of the quizzes along with the document they are placed in
+    pub fn find_all_by_original_quizzes(
+        conn: &mut PgConnection,
+        original_quiz_ids: &Vec<Uuid>,
+    ) -> Result<Vec<(Self, Uuid)>, Error> {
+        quiz_blocks::table
+            .inner_join(page_contents::table.inner_join(pages::table))
+            .filter(quiz_blocks::original_quiz_id.eq_any(original_quiz_ids))
+            .select((quiz_blocks::all_columns, pages::document_id))
+            .get_results(conn)
+    }
+
+    pub fn update_content(
+        conn: &mut PgConnection,
+        ids: &Vec<Uuid>,
+        question_data: &Value,
+        answer_data: &Value,
+        points: f64,
+    ) -> Result<Vec<Self>, Error> {
+        diesel::update(quiz_blocks::table.filter(quiz_blocks::id.eq_any(ids)))
+            .set((
+                quiz_blocks::question_data.eq(question_data),
+                quiz_blocks::answer_data.eq(answer_data),
+                quiz_blocks::points.eq(points),
+                quiz_blocks::updated_at.eq(get_now_as_secs()),
+            ))
+            .get_results(conn)
+    }
+
+    // Question data is kept since options of copies may be shuffled
+    pub fn update_answer_key(
+        conn: &mut PgConnection,
+        ids: &Vec<Uuid>,
+        answer_data: &Value,
+        points: f64,
+    ) -> Result<Vec<Self>, Error> {
+        diesel::update(quiz_blocks::table.filter(quiz_blocks::id.eq_any(ids)))
+            .set((
+                quiz_blocks::answer_data.eq(answer_data),
+                quiz_blocks::points.eq(points),
+                quiz_blocks::updated_at.eq(get_now_as_secs()),
+            ))
+            .get_results(conn)
+    }
+
+    // Options are shuffled in question data, answers still refer to option ids.
+    // Each quiz gets its own order, which is reproducible from the seed and the original quiz.
+    pub fn shuffle_options(&mut self, seed: i64) {
+        let key = match self.quiz_type {
+            QuizType::SingleChoice | QuizType::MultipleChoice | QuizType::SelectOption => "options",
+            _ => return,
+        };
+
+        let (quiz_seed, _) = self.original_quiz_id.unwrap_or(self.id).as_u64_pair();
+        let mut rng = StdRng::seed_from_u64(seed as u64 ^ quiz_seed);
+        if let Some(options) = self
+            .question_data
+            .get_mut(key)
+            .and_then(|options| options.as_array_mut())
+        {
+            options.shuffle(&mut rng);
+        }
+    }
+
+    // Score of an answer in points of this quiz, score given by teacher is used as it is
+    pub fn weighted_score(&self, answer: &QuizUserAnswer) -> f64 {
+        if let Some(manual_score) = answer.manual_score {
+            return manual_score;
+        }
+
+        answer.score * self.points
+    }
+
+    // Points deducted from the submission for incorrect picks of the answer. Negative marking
+    // is the fraction of quiz points deducted for each incorrect pick, nothing is deducted
+    // once the answer is scored by teacher.
+    pub fn negative_marking_penalty(&self, answer: &QuizUserAnswer, negative_marking: f64) -> f64 {
+        if answer.manual_score.is_some() {
+            return 0.0;
+        }
+
+        let total_incorrect_choice = count_incorrect_choices(
+            self.quiz_type,
+            self.answer_data.clone(),
+            answer.answer_data.clone(),
+        )
+        .unwrap_or_default();
+        negative_marking.abs() * self.points * total_incorrect_choice as f64
+    }
 }
 
 #[derive(Debug, Clone, Insertable, Queryable, SimpleObject, InputObject)]
@@ -149,9 +278,41 @@ pub struct QuizUserAnswer {
     pub updated_at: i64,
     #[graphql(skip_input)]
     pub created_at: i64,
+    // Score given by teacher in points of the quiz, overrides the auto score
+    #[graphql(skip)]
+    pub manual_score: Option<f64>,
+    #[graphql(skip)]
+    pub comment: Option<String>,
+    #[graphql(skip)]
+    pub graded_at: Option<i64>,
 }
 
 impl QuizUserAnswer {
+    // Score from 0 to 1, teacher's score takes precedence over the auto score
+    pub fn final_score(&self, points: f64) -> f64 {
+        match self.manual_score {
+            Some(manual_score) if points > 0.0 => manual_score / points,
+            Some(_) => 0.0,
+            None => self.score,
+        }
+    }
+
+    pub fn update_manual_score(
+        conn: &mut PgConnection,
+        quiz_id: Uuid,
+        user_id: i32,
+        manual_score: Option<f64>,
+        comment: Option<String>,
+    ) -> Result<Self, Error> {
+        diesel::update(quiz_user_answer::table.find((quiz_id, user_id)))
+            .set((
+                quiz_user_answer::manual_score.eq(manual_score),
+                quiz_user_answer::comment.eq(comment),
+                quiz_user_answer::graded_at.eq(get_now_as_secs()),
+            ))
+            .get_result(conn)
+    }
+
     pub fn upsert(conn: &mut PgConnection, mut item: Self) -> Result<Self, Error> {
         item.updated_at = get_now_as_secs();
         item.created_at = get_now_as_secs();
@@ -168,6 +329,17 @@ impl QuizUserAnswer {
             .get_result(conn)
     }
 
+    pub fn update_score(
+        conn: &mut PgConnection,
+        quiz_id: Uuid,
+        user_id: i32,
+        score: f64,
+    ) -> Result<Self, Error> {
+        diesel::update(quiz_user_answer::table.find((quiz_id, user_id)))
+            .set(quiz_user_answer::score.eq(score))
+            .get_result(conn)
+    }
+
     pub fn find(conn: &mut PgConnection, quiz_id: Uuid, user_id: i32) -> Result<Self, Error> {
         quiz_user_answer::table
             .find((quiz_id, user_id))
@@ -268,16 +440,43 @@ pub fn get_auto_store(quiz_type: QuizType, expected_answer: Value, answer: Value
             let fill_in_blank_expected_answer: FillInBlankAnswerData =
                 serde_json::from_value(expected_answer).ok()?;
             let user_answer: FillInBlankUserAnswerData = serde_json::from_value(answer).ok()?;
-            let has_correct_answer =
-                fill_in_blank_expected_answer
-                    .expected_answers
-                    .iter()
-                    .any(|expected_answer| {
-                        expected_answer.content.to_lowercase().trim()
-                            == user_answer.answer.to_lowercase().trim()
-                    });
+            let best_score = fill_in_blank_expected_answer
+                .expected_answers
+                .iter()
+                .filter(|expected_answer| expected_answer.is_match(&user_answer.answer))
+                .map(|expected_answer| expected_answer.score_weight.clamp(0.0, 1.0))
+                .fold(0.0, f64::max);
+            return Some(best_score);
+        }
+        QuizType::Ordering => {
+            let ordering_expected_answer: OrderingAnswerData =
+                serde_json::from_value(expected_answer).ok()?;
+            let user_answer: OrderingUserAnswerData = serde_json::from_value(answer).ok()?;
+            return Some(ordering_expected_answer.score(&user_answer.order));
+        }
+        QuizType::Matching => {
+            let matching_expected_answer: MatchingAnswerData =
+                serde_json::from_value(expected_answer).ok()?;
+            let user_answer: MatchingUserAnswerData = serde_json::from_value(answer).ok()?;
+
+            if matching_expected_answer.expected_pairs.is_empty() {
+                return Some(0.0);
+            }
 
-            if has_correct_answer {
+            let total_correct_pair = matching_expected_answer
+                .expected_pairs
+                .iter()
+                .filter(|(left, right)| user_answer.pairs.get(left) == Some(right))
+                .count();
+            return Some(
+                total_correct_pair as f64 / matching_expected_answer.expected_pairs.len() as f64,
+            );
+        }
+        QuizType::Numeric => {
+            let numeric_expected_answer: NumericAnswerData =
+                serde_json::from_value(expected_answer).ok()?;
+            let user_answer: NumericUserAnswerData = serde_json::from_value(answer).ok()?;
+            if numeric_expected_answer.is_correct(&user_answer.answer) {
                 return Some(1.0);
             }
         }
@@ -287,6 +486,41 @@ pub fn get_auto_store(quiz_type: QuizType, expected_answer: Value, answer: Value
     Some(0.0)
 }
 
+pub fn count_incorrect_choices(
+    quiz_type: QuizType,
+    expected_answer: Value,
+    answer: Value,
+) -> Option<usize> {
+    match quiz_type {
+        QuizType::SingleChoice | QuizType::MultipleChoice => {
+            let choice_expected_answer: ChoiceAnswerData =
+                serde_json::from_value(expected_answer).ok()?;
+            let user_answer: ChoiceUserAnswerData = serde_json::from_value(answer).ok()?;
+            Some(
+                user_answer
+                    .choices
+                    .iter()
+                    .filter(|choice| !choice_expected_answer.expected_choices.contains(choice))
+                    .count(),
+            )
+        }
+        QuizType::SelectOption => {
+            let choice_expected_answer: SelectAnswerData =
+                serde_json::from_value(expected_answer).ok()?;
+            let user_answer: SelectUserAnswerData = serde_json::from_value(answer).ok()?;
+            if choice_expected_answer
+                .expected_choices
+                .contains(&user_answer.choice)
+            {
+                Some(0)
+            } else {
+                Some(1)
+            }
+        }
+        _ => Some(0),
+    }
+}
+
 // Writing Block
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 #[serde(rename_all = "camelCase")]
@@ -347,10 +581,92 @@ pub struct FillInBlankQuestionData {
     pub content: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "camelCase")]
+pub enum FillInBlankMatchMode {
+    // Case-insensitive, ignore leading and trailing spaces
+    Exact,
+    CaseSensitive,
+    // Whole answer must match the regex pattern in content
+    Regex,
+    // Ignore diacritics (e.g. "Việt Nam" == "viet nam"), case and extra spaces
+    AccentInsensitive,
+    // Case-insensitive, any run of spaces counts as one space
+    WhitespaceCollapsing,
+    // Case-insensitive, accept answer within max_distance edits
+    Levenshtein,
+}
+
+impl Default for FillInBlankMatchMode {
+    fn default() -> Self {
+        Self::Exact
+    }
+}
+
+fn default_score_weight() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct FillInBlankExpectedAnswer {
+    pub id: Uuid,
+    pub content: String,
+    #[serde(default)]
+    pub match_mode: FillInBlankMatchMode,
+    // Score (0 -> 1) student earns when matching this answer
+    #[serde(default = "default_score_weight")]
+    pub score_weight: f64,
+    // Only used by Levenshtein mode
+    #[serde(default)]
+    pub max_distance: u32,
+}
+
+impl FillInBlankExpectedAnswer {
+    pub fn new(content: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            content,
+            match_mode: FillInBlankMatchMode::default(),
+            score_weight: default_score_weight(),
+            max_distance: 0,
+        }
+    }
+
+    pub fn is_match(&self, answer: &str) -> bool {
+        let expected = self.content.as_str();
+        match self.match_mode {
+            FillInBlankMatchMode::Exact => {
+                expected.trim().to_lowercase() == answer.trim().to_lowercase()
+            }
+            FillInBlankMatchMode::CaseSensitive => expected.trim() == answer.trim(),
+            FillInBlankMatchMode::Regex => RegexBuilder::new(&format!("^(?:{expected})$"))
+                .size_limit(1 << 20)
+                .build()
+                .map_or(false, |regex| regex.is_match(answer.trim())),
+            FillInBlankMatchMode::AccentInsensitive => {
+                collapse_whitespace(&remove_diacritics(expected)).to_lowercase()
+                    == collapse_whitespace(&remove_diacritics(answer)).to_lowercase()
+            }
+            FillInBlankMatchMode::WhitespaceCollapsing => {
+                collapse_whitespace(expected).to_lowercase()
+                    == collapse_whitespace(answer).to_lowercase()
+            }
+            FillInBlankMatchMode::Levenshtein => {
+                let distance = levenshtein_distance(
+                    &expected.trim().to_lowercase(),
+                    &answer.trim().to_lowercase(),
+                );
+                distance <= self.max_distance as usize
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 #[serde(rename_all = "camelCase")]
 pub struct FillInBlankAnswerData {
-    pub expected_answers: Vec<ChoiceOption>,
+    pub expected_answers: Vec<FillInBlankExpectedAnswer>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -358,3 +674,955 @@ pub struct FillInBlankAnswerData {
 pub struct FillInBlankUserAnswerData {
     pub answer: String,
 }
+
+// Ordering
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderingScoringRule {
+    // Full score only if the whole order is correct
+    Exact,
+    // Longest run of items kept in the correct relative order
+    LongestCorrectSubsequence,
+    // Pairs of neighbour items placed next to each other in the correct order
+    AdjacentPair,
+}
+
+impl Default for OrderingScoringRule {
+    fn default() -> Self {
+        Self::Exact
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderingQuestionData {
+    pub question: String,
+    pub items: Vec<ChoiceOption>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderingAnswerData {
+    pub expected_order: Vec<Uuid>,
+    #[serde(default)]
+    pub scoring_rule: OrderingScoringRule,
+}
+
+impl OrderingAnswerData {
+    pub fn score(&self, order: &[Uuid]) -> f64 {
+        let expected_order = &self.expected_order;
+        if expected_order.is_empty() {
+            return 0.0;
+        }
+
+        // Each item can only be placed once, repeated items are not a valid order
+        let mut placed_items = HashSet::new();
+        if !order.iter().all(|item| placed_items.insert(item)) {
+            return 0.0;
+        }
+
+        match self.scoring_rule {
+            OrderingScoringRule::Exact => {
+                if expected_order.as_slice() == order {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            OrderingScoringRule::LongestCorrectSubsequence => {
+                // Classic LCS between the expected order and the user order
+                let mut lengths = vec![vec![0usize; order.len() + 1]; expected_order.len() + 1];
+                for (i, expected_item) in expected_order.iter().enumerate() {
+                    for (j, item) in order.iter().enumerate() {
+                        lengths[i + 1][j + 1] = if expected_item == item {
+                            lengths[i][j] + 1
+                        } else {
+                            lengths[i][j + 1].max(lengths[i + 1][j])
+                        };
+                    }
+                }
+                lengths[expected_order.len()][order.len()] as f64 / expected_order.len() as f64
+            }
+            OrderingScoringRule::AdjacentPair => {
+                if expected_order.len() == 1 {
+                    return if expected_order.as_slice() == order {
+                        1.0
+                    } else {
+                        0.0
+                    };
+                }
+
+                let total_correct_pair = expected_order
+                    .windows(2)
+                    .filter(|expected_pair| order.windows(2).any(|pair| pair == *expected_pair))
+                    .count();
+                total_correct_pair as f64 / (expected_order.len() - 1) as f64
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderingUserAnswerData {
+    pub order: Vec<Uuid>,
+}
+
+// Matching
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchingQuestionData {
+    pub question: String,
+    pub left_items: Vec<ChoiceOption>,
+    pub right_items: Vec<ChoiceOption>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchingAnswerData {
+    // Left Item Id -> Right Item Id
+    pub expected_pairs: HashMap<Uuid, Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchingUserAnswerData {
+    // Left Item Id -> Right Item Id
+    pub pairs: HashMap<Uuid, Uuid>,
+}
+
+// Numeric
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "camelCase")]
+pub enum NumericToleranceType {
+    Absolute,
+    // Tolerance is a fraction of the expected value, e.g. 0.05 = 5%
+    Relative,
+}
+
+impl Default for NumericToleranceType {
+    fn default() -> Self {
+        Self::Absolute
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "camelCase")]
+pub enum NumericDecimalSeparator {
+    // Guess from the answer: "1,5" is 1.5 but "1,234" is 1234
+    Auto,
+    // "1,234" is 1234, commas only group thousands
+    Dot,
+    // "1,234" is 1.234, dots only group thousands
+    Comma,
+}
+
+impl Default for NumericDecimalSeparator {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct NumericQuestionData {
+    pub question: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct NumericAnswerData {
+    pub expected_value: f64,
+    #[serde(default)]
+    pub tolerance: f64,
+    #[serde(default)]
+    pub tolerance_type: NumericToleranceType,
+    // Empty means the answer is a bare number, any unit will be ignored
+    #[serde(default)]
+    pub accepted_units: Vec<String>,
+    #[serde(default)]
+    pub decimal_separator: NumericDecimalSeparator,
+}
+
+impl NumericAnswerData {
+    pub fn is_correct(&self, answer: &str) -> bool {
+        let Some(value) = self.parse_answer(answer) else {
+            return false;
+        };
+
+        let allowed_diff = match self.tolerance_type {
+            NumericToleranceType::Absolute => self.tolerance.abs(),
+            NumericToleranceType::Relative => (self.tolerance * self.expected_value).abs(),
+        };
+        // Keep a tiny slack so 0.1 + 0.2 style rounding doesn't fail an exact answer
+        let epsilon = f64::EPSILON * self.expected_value.abs().max(1.0) * 4.0;
+        (value - self.expected_value).abs() <= allowed_diff + epsilon
+    }
+
+    pub fn parse_answer(&self, answer: &str) -> Option<f64> {
+        let answer = answer.trim();
+        let lowercase_answer = answer.to_lowercase();
+        let mut units: Vec<String> = self
+            .accepted_units
+            .iter()
+            .map(|unit| unit.trim().to_lowercase())
+            .filter(|unit| !unit.is_empty())
+            .collect();
+        // Prefer the longest unit, so "km" is stripped before "m"
+        units.sort_by_key(|unit| std::cmp::Reverse(unit.chars().count()));
+
+        let separator = self.decimal_separator;
+        if let Some(value) = parse_number(answer, separator) {
+            return Some(value);
+        }
+
+        for unit in units.iter() {
+            if let Some(number) = lowercase_answer.strip_suffix(unit.as_str()) {
+                if let Some(value) = parse_number(number, separator) {
+                    return Some(value);
+                }
+            }
+        }
+
+        if units.is_empty() {
+            // Ignore any trailing unit like "5 cm" when teacher doesn't require a unit
+            let number = answer.trim_end_matches(|c: char| {
+                !c.is_ascii_digit() && !['.', ','].contains(&c) && c != '/'
+            });
+            if number.len() != answer.len() {
+                return parse_number(number, separator);
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct NumericUserAnswerData {
+    pub answer: String,
+}
+
+// Parse number leniently: "0.5", ".5", "0,5", "1,000.5", "1.000,5", "5e-1", "1/2", "1 1/2"
+pub fn parse_number(raw: &str, separator: NumericDecimalSeparator) -> Option<f64> {
+    let raw = raw.trim().replace(['\u{2212}', '\u{2013}'], "-");
+    if raw.is_empty() {
+        return None;
+    }
+
+    if raw.contains('/') {
+        // Spaces around the fraction bar, e.g. "1 / 2"
+        let raw = raw
+            .split('/')
+            .map(str::trim)
+            .collect::<Vec<&str>>()
+            .join("/");
+        let parts: Vec<&str> = raw.split_whitespace().collect();
+        return match parts.as_slice() {
+            [fraction] => parse_fraction(fraction, separator),
+            [whole, fraction] if !whole.contains('/') => {
+                let whole = parse_decimal(whole, separator)?;
+                let fraction = parse_fraction(fraction, separator)?;
+                if fraction < 0.0 {
+                    return None;
+                }
+                if whole.is_sign_negative() {
+                    Some(whole - fraction)
+                } else {
+                    Some(whole + fraction)
+                }
+            }
+            _ => None,
+        };
+    }
+
+    parse_decimal(&raw.split_whitespace().collect::<String>(), separator)
+}
+
+fn parse_fraction(raw: &str, separator: NumericDecimalSeparator) -> Option<f64> {
+    let (numerator, denominator) = raw.split_once('/')?;
+    let numerator = parse_decimal(numerator.trim(), separator)?;
+    let denominator = parse_decimal(denominator.trim(), separator)?;
+    if denominator == 0.0 {
+        return None;
+    }
+
+    Some(numerator / denominator)
+}
+
+fn parse_decimal(raw: &str, separator: NumericDecimalSeparator) -> Option<f64> {
+    let normalized = match separator {
+        NumericDecimalSeparator::Auto => guess_decimal_separator(raw),
+        NumericDecimalSeparator::Dot => remove_thousand_separators(raw, ',', '.')?,
+        NumericDecimalSeparator::Comma => remove_thousand_separators(raw, '.', ',')?,
+    };
+
+    // Rust parser also accepts "inf" or "NaN" which are never valid answers
+    if !normalized
+        .chars()
+        .all(|c| c.is_ascii_digit() || ['.', '-', '+', 'e', 'E'].contains(&c))
+    {
+        return None;
+    }
+
+    normalized
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+}
+
+fn guess_decimal_separator(raw: &str) -> String {
+    let last_dot = raw.rfind('.');
+    let last_comma = raw.rfind(',');
+    match (last_dot, last_comma) {
+        // The separator which comes last is the decimal one, the other groups thousands
+        (Some(dot), Some(comma)) if dot > comma => raw.replace(',', ""),
+        (Some(_), Some(_)) => raw.replace('.', "").replace(',', "."),
+        (None, Some(comma)) => {
+            // A single comma is a decimal one unless exactly 3 digits follow it
+            let is_thousand_separator = raw.matches(',').count() > 1 || {
+                let (integer_part, decimal_part) = raw.split_at(comma);
+                let integer_part = integer_part.trim_start_matches(['-', '+']);
+                decimal_part.len() == 4
+                    && !integer_part.is_empty()
+                    && integer_part.trim_start_matches('0').len() == integer_part.len()
+            };
+            if is_thousand_separator {
+                raw.replace(',', "")
+            } else {
+                raw.replace(',', ".")
+            }
+        }
+        _ => raw.to_string(),
+    }
+}
+
+// Thousand separators must group exactly 3 digits, so "1,5" is rejected rather than
+// read as 15 when the decimal separator is a dot
+fn remove_thousand_separators(raw: &str, thousand: char, decimal: char) -> Option<String> {
+    let (integer_part, decimal_part) = match raw.split_once(decimal) {
+        Some((integer_part, decimal_part)) => (integer_part, Some(decimal_part)),
+        None => (raw, None),
+    };
+    let mut groups = integer_part.split(thousand);
+    let first_group = groups.next().unwrap_or_default();
+    let mut has_groups = false;
+    for group in groups {
+        if group.len() != 3 {
+            return None;
+        }
+        has_groups = true;
+    }
+    if has_groups && first_group.trim_start_matches(['-', '+']).is_empty() {
+        return None;
+    }
+
+    let mut normalized = integer_part.replace(thousand, "");
+    if let Some(decimal_part) = decimal_part {
+        normalized.push('.');
+        normalized.push_str(decimal_part);
+    }
+    Some(normalized)
+}
+
+fn collapse_whitespace(content: &str) -> String {
+    content.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn remove_diacritics(content: &str) -> String {
+    content
+        .nfd()
+        .filter(|c| !unicode_normalization::char::is_combining_mark(*c))
+        // "đ" has no decomposition in unicode
+        .map(|c| match c {
+            'đ' => 'd',
+            'Đ' => 'D',
+            _ => c,
+        })
+        .collect()
+}
+
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current_row = vec![i + 1; b_chars.len() + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution_cost = if a_char == *b_char { 0 } else { 1 };
+            current_row[j + 1] = (previous_row[j] + substitution_cost)
+                .min(previous_row[j + 1] + 1)
+                .min(current_row[j] + 1);
+        }
+        previous_row = current_row;
+    }
+
+    previous_row[b_chars.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+
+    use super::*;
+
+    fn ids(count: usize) -> Vec<Uuid> {
+        (0..count).map(|_| Uuid::new_v4()).collect()
+    }
+
+    fn ordering(expected_order: &[Uuid], scoring_rule: OrderingScoringRule) -> OrderingAnswerData {
+        OrderingAnswerData {
+            expected_order: expected_order.to_vec(),
+            scoring_rule,
+        }
+    }
+
+    #[test]
+    fn ordering_exact_requires_whole_order() {
+        let items = ids(3);
+        let answer = ordering(&items, OrderingScoringRule::Exact);
+        assert_eq!(answer.score(&items), 1.0);
+        assert_eq!(answer.score(&[items[0], items[2], items[1]]), 0.0);
+        assert_eq!(answer.score(&items[..2]), 0.0);
+        assert_eq!(answer.score(&[]), 0.0);
+    }
+
+    #[test]
+    fn ordering_longest_correct_subsequence() {
+        let items = ids(4);
+        let answer = ordering(&items, OrderingScoringRule::LongestCorrectSubsequence);
+        assert_eq!(answer.score(&items), 1.0);
+        // a c b d keeps a b d in order
+        assert_eq!(
+            answer.score(&[items[0], items[2], items[1], items[3]]),
+            0.75
+        );
+        // d c b a keeps only one item in order
+        let reversed: Vec<Uuid> = items.iter().rev().cloned().collect();
+        assert_eq!(answer.score(&reversed), 0.25);
+        assert_eq!(answer.score(&[]), 0.0);
+    }
+
+    #[test]
+    fn ordering_adjacent_pair() {
+        let items = ids(4);
+        let answer = ordering(&items, OrderingScoringRule::AdjacentPair);
+        assert_eq!(answer.score(&items), 1.0);
+        // c d a b keeps (a, b) and (c, d) next to each other
+        assert_eq!(
+            answer.score(&[items[2], items[3], items[0], items[1]]),
+            2.0 / 3.0
+        );
+        let reversed: Vec<Uuid> = items.iter().rev().cloned().collect();
+        assert_eq!(answer.score(&reversed), 0.0);
+
+        let single_item = ordering(&items[..1], OrderingScoringRule::AdjacentPair);
+        assert_eq!(single_item.score(&items[..1]), 1.0);
+        assert_eq!(single_item.score(&[]), 0.0);
+    }
+
+    #[test]
+    fn ordering_with_repeated_items_scores_zero() {
+        let items = ids(3);
+        let repeated = [items[0], items[1], items[0], items[1], items[2]];
+        for scoring_rule in [
+            OrderingScoringRule::Exact,
+            OrderingScoringRule::LongestCorrectSubsequence,
+            OrderingScoringRule::AdjacentPair,
+        ] {
+            let answer = ordering(&items, scoring_rule);
+            assert_eq!(answer.score(&repeated), 0.0, "{:?}", scoring_rule);
+        }
+    }
+
+    #[test]
+    fn ordering_without_expected_items_scores_zero() {
+        let answer = ordering(&[], OrderingScoringRule::LongestCorrectSubsequence);
+        assert_eq!(answer.score(&ids(2)), 0.0);
+    }
+
+    #[test]
+    fn ordering_auto_score_from_json() {
+        let items = ids(2);
+        let expected_answer = serde_json::json!({ "expectedOrder": items });
+        let reversed: Vec<Uuid> = items.iter().rev().cloned().collect();
+        assert_eq!(
+            try_get_auto_score(
+                QuizType::Ordering,
+                expected_answer.clone(),
+                serde_json::json!({ "order": items })
+            ),
+            1.0
+        );
+        assert_eq!(
+            try_get_auto_score(
+                QuizType::Ordering,
+                expected_answer.clone(),
+                serde_json::json!({ "order": reversed })
+            ),
+            0.0
+        );
+        assert_eq!(
+            try_get_auto_score(QuizType::Ordering, expected_answer, serde_json::json!({})),
+            0.0
+        );
+    }
+
+    fn matching_score(expected_pairs: Value, pairs: Value) -> f64 {
+        try_get_auto_score(
+            QuizType::Matching,
+            serde_json::json!({ "expectedPairs": expected_pairs }),
+            serde_json::json!({ "pairs": pairs }),
+        )
+    }
+
+    fn expected_pairs_of(left: &[Uuid], right: &[Uuid]) -> HashMap<Uuid, Uuid> {
+        left.iter().cloned().zip(right.iter().cloned()).collect()
+    }
+
+    #[test]
+    fn matching_scores_each_correct_pair() {
+        let (left, right) = (ids(4), ids(4));
+        let expected_pairs = serde_json::json!(expected_pairs_of(&left, &right));
+
+        let all_correct: HashMap<Uuid, Uuid> = expected_pairs_of(&left, &right);
+        assert_eq!(
+            matching_score(expected_pairs.clone(), serde_json::json!(all_correct)),
+            1.0
+        );
+
+        let mut half_correct = all_correct.clone();
+        half_correct.insert(left[0], right[1]);
+        half_correct.insert(left[1], right[0]);
+        assert_eq!(
+            matching_score(expected_pairs.clone(), serde_json::json!(half_correct)),
+            0.5
+        );
+
+        let mut unanswered = all_correct.clone();
+        unanswered.remove(&left[3]);
+        assert_eq!(
+            matching_score(expected_pairs.clone(), serde_json::json!(unanswered)),
+            0.75
+        );
+        assert_eq!(matching_score(expected_pairs, serde_json::json!({})), 0.0);
+    }
+
+    #[test]
+    fn matching_same_right_item_counts_once() {
+        let (left, right) = (ids(3), ids(3));
+        let expected_pairs = serde_json::json!(expected_pairs_of(&left, &right));
+        let all_to_first: HashMap<Uuid, Uuid> = left.iter().map(|id| (*id, right[0])).collect();
+        assert_eq!(
+            matching_score(expected_pairs, serde_json::json!(all_to_first)),
+            1.0 / 3.0
+        );
+    }
+
+    #[test]
+    fn matching_extra_pairs_are_ignored() {
+        let (left, right) = (ids(2), ids(2));
+        let expected_pairs = serde_json::json!(expected_pairs_of(&left, &right));
+        let mut pairs = expected_pairs_of(&left, &right);
+        pairs.insert(Uuid::new_v4(), right[0]);
+        assert_eq!(
+            matching_score(expected_pairs, serde_json::json!(pairs)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn matching_without_expected_pairs_scores_zero() {
+        let (left, right) = (ids(1), ids(1));
+        assert_eq!(
+            matching_score(
+                serde_json::json!({}),
+                serde_json::json!(expected_pairs_of(&left, &right))
+            ),
+            0.0
+        );
+        assert_eq!(
+            matching_score(serde_json::json!([]), serde_json::json!({})),
+            0.0
+        );
+    }
+
+    #[test]
+    fn parse_number_decimal_separators() {
+        assert_eq!(
+            parse_number("0.5", NumericDecimalSeparator::Auto),
+            Some(0.5)
+        );
+        assert_eq!(parse_number(".5", NumericDecimalSeparator::Auto), Some(0.5));
+        assert_eq!(
+            parse_number("1,5", NumericDecimalSeparator::Auto),
+            Some(1.5)
+        );
+        assert_eq!(
+            parse_number("0,500", NumericDecimalSeparator::Auto),
+            Some(0.5)
+        );
+        assert_eq!(
+            parse_number("1,000", NumericDecimalSeparator::Auto),
+            Some(1000.0)
+        );
+        assert_eq!(
+            parse_number("1,000,000", NumericDecimalSeparator::Auto),
+            Some(1000000.0)
+        );
+        assert_eq!(
+            parse_number("1,000.5", NumericDecimalSeparator::Auto),
+            Some(1000.5)
+        );
+        assert_eq!(
+            parse_number("1.000,5", NumericDecimalSeparator::Auto),
+            Some(1000.5)
+        );
+        assert_eq!(
+            parse_number("1 000", NumericDecimalSeparator::Auto),
+            Some(1000.0)
+        );
+        assert_eq!(
+            parse_number(" +5 ", NumericDecimalSeparator::Auto),
+            Some(5.0)
+        );
+        assert_eq!(
+            parse_number("5e-1", NumericDecimalSeparator::Auto),
+            Some(0.5)
+        );
+        assert_eq!(
+            parse_number("\u{2212}3", NumericDecimalSeparator::Auto),
+            Some(-3.0)
+        );
+    }
+
+    #[test]
+    fn parse_number_guesses_single_comma() {
+        let auto = NumericDecimalSeparator::Auto;
+        assert_eq!(parse_number("1,234", auto), Some(1234.0));
+        assert_eq!(parse_number("-1,234", auto), Some(-1234.0));
+        assert_eq!(parse_number("1,23", auto), Some(1.23));
+        assert_eq!(parse_number("1,2345", auto), Some(1.2345));
+        assert_eq!(parse_number("12,5", auto), Some(12.5));
+        assert_eq!(parse_number(",234", auto), Some(0.234));
+    }
+
+    #[test]
+    fn parse_number_with_decimal_separator() {
+        let dot = NumericDecimalSeparator::Dot;
+        assert_eq!(parse_number("1,234", dot), Some(1234.0));
+        assert_eq!(parse_number("1,234.5", dot), Some(1234.5));
+        assert_eq!(parse_number("1.5", dot), Some(1.5));
+        assert_eq!(parse_number("1,5", dot), None);
+        assert_eq!(parse_number("1.000,5", dot), None);
+
+        let comma = NumericDecimalSeparator::Comma;
+        assert_eq!(parse_number("1,234", comma), Some(1.234));
+        assert_eq!(parse_number("1.234,5", comma), Some(1234.5));
+        assert_eq!(parse_number("-1.000.000", comma), Some(-1000000.0));
+        assert_eq!(parse_number("1 1/2", comma), Some(1.5));
+        assert_eq!(parse_number("1.5", comma), None);
+        assert_eq!(parse_number("1,2,3", comma), None);
+        assert_eq!(parse_number(".234", comma), None);
+    }
+
+    #[test]
+    fn parse_number_fractions() {
+        assert_eq!(
+            parse_number("1/2", NumericDecimalSeparator::Auto),
+            Some(0.5)
+        );
+        assert_eq!(
+            parse_number("-1/4", NumericDecimalSeparator::Auto),
+            Some(-0.25)
+        );
+        assert_eq!(
+            parse_number("1 / 2", NumericDecimalSeparator::Auto),
+            Some(0.5)
+        );
+        assert_eq!(
+            parse_number("1 1/2", NumericDecimalSeparator::Auto),
+            Some(1.5)
+        );
+        assert_eq!(
+            parse_number("-1 1/2", NumericDecimalSeparator::Auto),
+            Some(-1.5)
+        );
+        assert_eq!(
+            parse_number("1,5/3", NumericDecimalSeparator::Auto),
+            Some(0.5)
+        );
+        assert_eq!(parse_number("1/0", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("0/0", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("1 -1/2", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("1/2/3", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("1/2 1/2", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("/2", NumericDecimalSeparator::Auto), None);
+    }
+
+    #[test]
+    fn parse_number_rejects_invalid_input() {
+        assert_eq!(parse_number("", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("   ", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("abc", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number(".", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("-", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("inf", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("NaN", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("1e400", NumericDecimalSeparator::Auto), None);
+        assert_eq!(parse_number("1.2.3", NumericDecimalSeparator::Auto), None);
+        assert_eq!(
+            parse_number("\u{0661}", NumericDecimalSeparator::Auto),
+            None
+        );
+    }
+
+    fn numeric(expected_value: f64, tolerance: f64, accepted_units: &[&str]) -> NumericAnswerData {
+        NumericAnswerData {
+            expected_value,
+            tolerance,
+            tolerance_type: NumericToleranceType::Absolute,
+            accepted_units: accepted_units.iter().map(|unit| unit.to_string()).collect(),
+            decimal_separator: NumericDecimalSeparator::Auto,
+        }
+    }
+
+    #[test]
+    fn numeric_tolerance() {
+        let answer = numeric(10.0, 0.5, &[]);
+        assert!(answer.is_correct("10"));
+        assert!(answer.is_correct("10.5"));
+        assert!(answer.is_correct("9,5"));
+        assert!(!answer.is_correct("10.51"));
+
+        let exact = numeric(0.3, 0.0, &[]);
+        assert!(exact.is_correct("0.3"));
+        assert!(!exact.is_correct("0.31"));
+
+        let relative = NumericAnswerData {
+            tolerance_type: NumericToleranceType::Relative,
+            ..numeric(-200.0, 0.05, &[])
+        };
+        assert!(relative.is_correct("-210"));
+        assert!(!relative.is_correct("-211"));
+        assert!(!relative.is_correct("200"));
+
+        let zero = NumericAnswerData {
+            tolerance_type: NumericToleranceType::Relative,
+            ..numeric(0.0, 0.05, &[])
+        };
+        assert!(zero.is_correct("0"));
+        assert!(zero.is_correct("-0"));
+        assert!(!zero.is_correct("0.001"));
+    }
+
+    #[test]
+    fn numeric_units() {
+        let answer = numeric(5.0, 0.0, &["km", "m", " "]);
+        assert!(answer.is_correct("5"));
+        assert!(answer.is_correct("5m"));
+        assert!(answer.is_correct("5 KM"));
+        assert!(!answer.is_correct("5 cm"));
+        assert!(!answer.is_correct("km"));
+
+        let without_unit = numeric(0.5, 0.0, &[]);
+        assert!(without_unit.is_correct("0.5 cm"));
+        assert!(without_unit.is_correct("1/2 cup"));
+        assert!(!without_unit.is_correct("cm"));
+        assert!(!without_unit.is_correct(""));
+    }
+
+    #[test]
+    fn numeric_comma_decimal_separator() {
+        let answer = NumericAnswerData {
+            decimal_separator: NumericDecimalSeparator::Comma,
+            ..numeric(1.234, 0.0, &["kg"])
+        };
+        assert!(answer.is_correct("1,234"));
+        assert!(answer.is_correct("1,234 kg"));
+        assert!(!answer.is_correct("1234"));
+
+        let data: NumericAnswerData = serde_json::from_value(serde_json::json!({
+            "expectedValue": 1234.0,
+        }))
+        .unwrap();
+        assert_eq!(data.decimal_separator, NumericDecimalSeparator::Auto);
+        assert!(data.is_correct("1,234"));
+    }
+
+    fn blank(content: &str, match_mode: FillInBlankMatchMode) -> FillInBlankExpectedAnswer {
+        FillInBlankExpectedAnswer {
+            match_mode,
+            ..FillInBlankExpectedAnswer::new(content.to_string())
+        }
+    }
+
+    #[test]
+    fn fill_in_blank_exact_and_case_sensitive() {
+        let exact = blank(" Hanoi ", FillInBlankMatchMode::Exact);
+        assert!(exact.is_match("hanoi"));
+        assert!(exact.is_match("  HANOI"));
+        assert!(!exact.is_match("Ha noi"));
+        assert!(!exact.is_match(""));
+
+        let case_sensitive = blank("Hanoi", FillInBlankMatchMode::CaseSensitive);
+        assert!(case_sensitive.is_match(" Hanoi "));
+        assert!(!case_sensitive.is_match("hanoi"));
+
+        let empty = blank("", FillInBlankMatchMode::Exact);
+        assert!(empty.is_match("  "));
+        assert!(!empty.is_match("a"));
+    }
+
+    #[test]
+    fn fill_in_blank_regex_matches_whole_answer() {
+        let regex = blank("colou?r|hue", FillInBlankMatchMode::Regex);
+        assert!(regex.is_match("color"));
+        assert!(regex.is_match(" colour "));
+        assert!(regex.is_match("hue"));
+        assert!(!regex.is_match("my colour"));
+        assert!(!regex.is_match("colors"));
+
+        let invalid = blank("(unclosed", FillInBlankMatchMode::Regex);
+        assert!(!invalid.is_match("(unclosed"));
+
+        let too_large = blank("\\w{1000}{1000}", FillInBlankMatchMode::Regex);
+        assert!(!too_large.is_match("a"));
+    }
+
+    #[test]
+    fn fill_in_blank_accent_insensitive() {
+        let answer = blank("Việt  Nam", FillInBlankMatchMode::AccentInsensitive);
+        assert!(answer.is_match("viet nam"));
+        assert!(answer.is_match(" VIỆT NAM "));
+        assert!(answer.is_match("Viet\tNam"));
+        assert!(!answer.is_match("vietnam"));
+
+        let precomposed = blank("Đà Nẵng", FillInBlankMatchMode::AccentInsensitive);
+        // "a" followed by a combining breve and tilde
+        assert!(precomposed.is_match("\u{0110}a\u{0300} Na\u{0306}\u{0303}ng"));
+        assert!(precomposed.is_match("da nang"));
+    }
+
+    #[test]
+    fn fill_in_blank_whitespace_collapsing() {
+        let answer = blank("New York", FillInBlankMatchMode::WhitespaceCollapsing);
+        assert!(answer.is_match("  new \n york "));
+        assert!(!answer.is_match("newyork"));
+    }
+
+    #[test]
+    fn fill_in_blank_levenshtein() {
+        let answer = FillInBlankExpectedAnswer {
+            max_distance: 1,
+            ..blank("Café", FillInBlankMatchMode::Levenshtein)
+        };
+        assert!(answer.is_match("cafe"));
+        assert!(answer.is_match("CAFÉS"));
+        assert!(!answer.is_match("caffe "));
+        assert!(!answer.is_match("cafes"));
+
+        let exact = blank("cat", FillInBlankMatchMode::Levenshtein);
+        assert!(exact.is_match("CAT"));
+        assert!(!exact.is_match("cut"));
+    }
+
+    #[test]
+    fn fill_in_blank_best_matching_weight() {
+        let expected_answer = serde_json::json!({
+            "expectedAnswers": [
+                { "id": Uuid::new_v4(), "content": "paris" },
+                { "id": Uuid::new_v4(), "content": "par.*", "matchMode": "regex", "scoreWeight": 0.5 },
+                { "id": Uuid::new_v4(), "content": "p.*", "matchMode": "regex", "scoreWeight": 2.0 },
+                { "id": Uuid::new_v4(), "content": "x", "scoreWeight": -1.0 },
+            ]
+        });
+        let score = |answer: &str| {
+            try_get_auto_score(
+                QuizType::FillInBlank,
+                expected_answer.clone(),
+                serde_json::json!({ "answer": answer }),
+            )
+        };
+        assert_eq!(score("Paris"), 1.0);
+        // Weight above 1 is capped
+        assert_eq!(score("pxx"), 1.0);
+        assert_eq!(score("x"), 0.0);
+        assert_eq!(score("berlin"), 0.0);
+    }
+
+    #[test]
+    fn remove_diacritics_keeps_other_scripts() {
+        assert_eq!(remove_diacritics("Tiếng Việt"), "Tieng Viet");
+        assert_eq!(remove_diacritics("đường Đông"), "duong Dong");
+        assert_eq!(
+            remove_diacritics("Crème brûlée, niño"),
+            "Creme brulee, nino"
+        );
+        assert_eq!(remove_diacritics("日本語 😀"), "日本語 😀");
+        assert_eq!(remove_diacritics(""), "");
+    }
+
+    #[test]
+    fn levenshtein_distance_counts_chars() {
+        assert_eq!(levenshtein_distance("", ""), 0);
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+        assert_eq!(levenshtein_distance("abc", ""), 3);
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("flaw", "lawn"), 2);
+        assert_eq!(levenshtein_distance("café", "cafe"), 1);
+        assert_eq!(levenshtein_distance("日本", "日本語"), 1);
+    }
+
+    #[test]
+    fn weighted_score_and_negative_marking_penalty() {
+        let options = ids(3);
+        let quiz = QuizBuilder::default()
+            .page_content_id(Uuid::new_v4())
+            .creator_id(1)
+            .quiz_type(QuizType::MultipleChoice)
+            .question_data(Value::Null)
+            .answer_data(serde_json::json!({ "expectedChoices": [options[0]] }))
+            .points(2.0)
+            .build()
+            .unwrap();
+        let answer = |choices: &[Uuid]| {
+            let answer_data = serde_json::json!({ "choices": choices });
+            QuizUserAnswer {
+                quiz_id: quiz.id,
+                user_id: 1,
+                score: try_get_auto_score(
+                    quiz.quiz_type,
+                    quiz.answer_data.clone(),
+                    answer_data.clone(),
+                ),
+                answer_data,
+                updated_at: 0,
+                created_at: 0,
+                manual_score: None,
+                comment: None,
+                graded_at: None,
+            }
+        };
+
+        assert_eq!(quiz.weighted_score(&answer(&options[..1])), 2.0);
+        assert_eq!(quiz.weighted_score(&answer(&options[1..2])), 0.0);
+        assert_eq!(
+            quiz.negative_marking_penalty(&answer(&options[..1]), 0.25),
+            0.0
+        );
+        assert_eq!(
+            quiz.negative_marking_penalty(&answer(&options[1..2]), 0.25),
+            0.5
+        );
+        assert_eq!(quiz.negative_marking_penalty(&answer(&options), 0.25), 1.0);
+
+        let manual_answer = QuizUserAnswer {
+            manual_score: Some(1.5),
+            ..answer(&options[1..])
+        };
+        assert_eq!(quiz.weighted_score(&manual_answer), 1.5);
+        assert_eq!(quiz.negative_marking_penalty(&manual_answer, 1.0), 0.0);
+    }
+}
@@ -0,0 +1,232 @@
+use diesel::result::Error;
+use diesel::sql_types::Integer;
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::schema::{bank_quiz_tags, bank_quizzes};
+use super::{Quiz, QuizType};
+use crate::impl_enum_for_db;
+use crate::util::get_now_as_secs;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    FromPrimitive,
+    ToPrimitive,
+    AsExpression,
+    FromSqlRow,
+    Enum,
+    Serialize,
+    Deserialize,
+)]
+#[diesel(sql_type = Integer)]
+pub enum QuizDifficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl_enum_for_db!(QuizDifficulty);
+
+impl Default for QuizDifficulty {
+    fn default() -> Self {
+        Self::Easy
+    }
+}
+
+#[derive(Debug, Clone, Insertable, Queryable, SimpleObject)]
+#[graphql(complex)]
+#[diesel(table_name = bank_quizzes)]
+pub struct BankQuiz {
+    pub id: Uuid,
+    pub space_id: i32,
+    pub creator_id: i32,
+    // The quiz this bank entry was saved from
+    pub original_quiz_id: Option<Uuid>,
+    pub quiz_type: QuizType,
+    pub question_data: Value,
+    pub answer_data: Value,
+    pub points: f64,
+    pub difficulty: QuizDifficulty,
+    pub updated_at: i64,
+    pub created_at: i64,
+}
+
+impl BankQuiz {
+    pub fn from_quiz(
+        quiz: &Quiz,
+        space_id: i32,
+        creator_id: i32,
+        difficulty: QuizDifficulty,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            space_id,
+            creator_id,
+            original_quiz_id: Some(quiz.id),
+            quiz_type: quiz.quiz_type,
+            question_data: quiz.question_data.clone(),
+            answer_data: quiz.answer_data.clone(),
+            points: quiz.points,
+            difficulty,
+            updated_at: get_now_as_secs(),
+            created_at: get_now_as_secs(),
+        }
+    }
+
+    // New quiz block placed in a page, keeping the link to this bank entry.
+    // It's not a copy of the quiz the bank entry was saved from, so it has no original quiz.
+    pub fn to_quiz(&self, id: Uuid, page_content_id: Uuid, creator_id: i32) -> Quiz {
+        Quiz {
+            id,
+            page_content_id,
+            creator_id,
+            original_quiz_id: None,
+            quiz_type: self.quiz_type,
+            question_data: self.question_data.clone(),
+            answer_data: self.answer_data.clone(),
+            updated_at: get_now_as_secs(),
+            created_at: get_now_as_secs(),
+            points: self.points,
+            bank_quiz_id: Some(self.id),
+        }
+    }
+
+    pub fn insert(conn: &mut PgConnection, item: Self) -> Result<Self, Error> {
+        diesel::insert_into(bank_quizzes::table)
+            .values(item)
+            .get_result(conn)
+    }
+
+    pub fn update(
+        conn: &mut PgConnection,
+        id: Uuid,
+        data: UpdateBankQuizData,
+    ) -> Result<Self, Error> {
+        diesel::update(bank_quizzes::table.find(id))
+            .set((
+                bank_quizzes::question_data.eq(data.question_data),
+                bank_quizzes::answer_data.eq(data.answer_data),
+                bank_quizzes::points.eq(data.points),
+                bank_quizzes::difficulty.eq(data.difficulty),
+                bank_quizzes::updated_at.eq(get_now_as_secs()),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn remove(conn: &mut PgConnection, id: Uuid) -> Result<(), Error> {
+        diesel::delete(bank_quizzes::table.find(id)).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find(conn: &mut PgConnection, id: Uuid) -> Result<Self, Error> {
+        bank_quizzes::table.find(id).first(conn)
+    }
+
+    pub fn search(
+        conn: &mut PgConnection,
+        space_id: i32,
+        filter: &BankQuizFilter,
+    ) -> Result<Vec<Self>, Error> {
+        let mut query = bank_quizzes::table
+            .filter(bank_quizzes::space_id.eq(space_id))
+            .into_boxed();
+        if let Some(quiz_type) = filter.quiz_type {
+            query = query.filter(bank_quizzes::quiz_type.eq(quiz_type));
+        }
+        if let Some(difficulty) = filter.difficulty {
+            query = query.filter(bank_quizzes::difficulty.eq(difficulty));
+        }
+        for tag in filter.tags.iter() {
+            let tagged_ids = bank_quiz_tags::table
+                .filter(bank_quiz_tags::tag.eq(tag))
+                .select(bank_quiz_tags::bank_quiz_id);
+            query = query.filter(bank_quizzes::id.eq_any(tagged_ids));
+        }
+
+        let mut items: Vec<Self> = query
+            .order(bank_quizzes::updated_at.desc())
+            .get_results(conn)?;
+        if let Some(keyword) = &filter.keyword {
+            let keyword = keyword.to_lowercase();
+            items.retain(|item| {
+                item.question_data
+                    .to_string()
+                    .to_lowercase()
+                    .contains(&keyword)
+            });
+        }
+
+        Ok(items)
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct UpdateBankQuizData {
+    pub question_data: Value,
+    pub answer_data: Value,
+    pub points: f64,
+    pub difficulty: QuizDifficulty,
+}
+
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct BankQuizFilter {
+    pub quiz_type: Option<QuizType>,
+    pub difficulty: Option<QuizDifficulty>,
+    // Bank quizzes must have all of these tags
+    #[graphql(default)]
+    pub tags: Vec<String>,
+    // Searched in question content
+    pub keyword: Option<String>,
+}
+
+#[derive(Debug, Clone, Insertable, Queryable, SimpleObject)]
+#[diesel(table_name = bank_quiz_tags)]
+pub struct BankQuizTag {
+    pub bank_quiz_id: Uuid,
+    pub tag: String,
+    pub created_at: i64,
+}
+
+impl BankQuizTag {
+    pub fn new(bank_quiz_id: Uuid, tag: String) -> Self {
+        Self {
+            bank_quiz_id,
+            tag,
+            created_at: get_now_as_secs(),
+        }
+    }
+
+    pub fn replace_all(
+        conn: &mut PgConnection,
+        bank_quiz_id: Uuid,
+        tags: Vec<String>,
+    ) -> Result<Vec<Self>, Error> {
+        diesel::delete(bank_quiz_tags::table.filter(bank_quiz_tags::bank_quiz_id.eq(bank_quiz_id)))
+            .execute(conn)?;
+
+        let items: Vec<Self> = tags
+            .into_iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| Self::new(bank_quiz_id, tag))
+            .collect();
+        diesel::insert_into(bank_quiz_tags::table)
+            .values(&items)
+            .on_conflict_do_nothing()
+            .get_results(conn)
+    }
+
+    pub fn find_by_bank_quiz_ids(
+        conn: &mut PgConnection,
+        bank_quiz_ids: &Vec<Uuid>,
+    ) -> Result<Vec<Self>, Error> {
+        bank_quiz_tags::table
+            .filter(bank_quiz_tags::bank_quiz_id.eq_any(bank_quiz_ids))
+            .get_results(conn)
+    }
+}
@@ -1,11 +1,11 @@
 use diesel::result::Error;
-use diesel::sql_types::Jsonb;
-use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use diesel::sql_types::{Integer, Jsonb};
+use diesel::{BoolExpressionMethods, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
 use uuid::Uuid;
 
-use super::schema::{rubric_submissions, rubrics};
-use crate::impl_jsonb_for_db;
+use super::schema::{rubric_submissions, rubric_versions, rubrics};
 use crate::util::get_now_as_secs;
+use crate::{impl_enum_for_db, impl_jsonb_for_db};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Enum)]
 pub enum RubricType {
@@ -64,29 +64,218 @@ pub struct RubricTableData {
     pub items: Vec<Vec<RubricTableItem>>,
 }
 
+// Invalid part of a rubric table, indexes point to the offending cell
+#[derive(Debug, Clone)]
+pub struct RubricValidationError {
+    pub message: String,
+    pub criteria_index: Option<usize>,
+    pub level_index: Option<usize>,
+}
+
+impl RubricValidationError {
+    pub fn new(
+        message: impl Into<String>,
+        criteria_index: Option<usize>,
+        level_index: Option<usize>,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            criteria_index,
+            level_index,
+        }
+    }
+}
+
 impl RubricTableData {
+    // Selected level of point based rubric scores its own score, only point range rubric
+    // lets the teacher pick a score in the range of the level.
     pub fn total_rubric_score(&self) -> f64 {
         let mut score = 0.0;
 
         for (index, inner_items) in self.items.iter().enumerate() {
-            let percentage = self
-                .weighting_criteria
-                .get(index)
-                .unwrap_or(&Some(1.0))
-                .unwrap_or(1.0);
+            let percentage = self.get_weighting(index);
             for item in inner_items {
                 if item.user_pick.selected {
-                    score += item.user_pick.score * percentage;
+                    let item_score = match self.rubric_type {
+                        RubricType::PointBased => item.score,
+                        RubricType::PointRange => item.user_pick.score,
+                    };
+                    score += item_score * percentage;
                 }
             }
         }
 
         score
     }
+
+    // Highest score a submission can get, the best level of each criterion is picked
+    pub fn max_rubric_score(&self) -> f64 {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(index, inner_items)| {
+                let max_score = inner_items
+                    .iter()
+                    .map(|item| match self.rubric_type {
+                        RubricType::PointBased => item.score,
+                        RubricType::PointRange => item.score.max(item.to_score),
+                    })
+                    .fold(0.0, f64::max);
+                max_score * self.get_weighting(index)
+            })
+            .sum()
+    }
+
+    pub fn get_weighting(&self, criteria_index: usize) -> f64 {
+        self.weighting_criteria
+            .get(criteria_index)
+            .copied()
+            .flatten()
+            .unwrap_or(1.0)
+    }
+
+    pub fn validate(&self) -> Result<(), RubricValidationError> {
+        if self.criteria.is_empty() {
+            return Err(RubricValidationError::new(
+                "Rubric must have at least one criterion",
+                None,
+                None,
+            ));
+        }
+        if self.level.is_empty() {
+            return Err(RubricValidationError::new(
+                "Rubric must have at least one level",
+                None,
+                None,
+            ));
+        }
+        if self.items.len() != self.criteria.len() {
+            return Err(RubricValidationError::new(
+                format!(
+                    "Rubric has {} criteria but {} rows of items",
+                    self.criteria.len(),
+                    self.items.len()
+                ),
+                None,
+                None,
+            ));
+        }
+        if !self.weighting_criteria.is_empty()
+            && self.weighting_criteria.len() != self.criteria.len()
+        {
+            return Err(RubricValidationError::new(
+                format!(
+                    "Rubric has {} criteria but {} weightings",
+                    self.criteria.len(),
+                    self.weighting_criteria.len()
+                ),
+                None,
+                None,
+            ));
+        }
+
+        for (criteria_index, inner_items) in self.items.iter().enumerate() {
+            if let Some(weighting) = self
+                .weighting_criteria
+                .get(criteria_index)
+                .copied()
+                .flatten()
+            {
+                if !weighting.is_finite() || weighting < 0.0 {
+                    return Err(RubricValidationError::new(
+                        "Weighting must be a positive number",
+                        Some(criteria_index),
+                        None,
+                    ));
+                }
+            }
+
+            if inner_items.len() != self.level.len() {
+                return Err(RubricValidationError::new(
+                    format!(
+                        "Criterion has {} items but rubric has {} levels",
+                        inner_items.len(),
+                        self.level.len()
+                    ),
+                    Some(criteria_index),
+                    None,
+                ));
+            }
+
+            let mut selected_level = None;
+            for (level_index, item) in inner_items.iter().enumerate() {
+                let cell_error = |message: &str| {
+                    Err(RubricValidationError::new(
+                        message,
+                        Some(criteria_index),
+                        Some(level_index),
+                    ))
+                };
+
+                if !item.score.is_finite() || item.score < 0.0 {
+                    return cell_error("Score must be a positive number");
+                }
+                if self.rubric_type == RubricType::PointRange
+                    && (!item.to_score.is_finite() || item.to_score < item.score)
+                {
+                    return cell_error("To score must not be less than from score");
+                }
+
+                if !item.user_pick.selected {
+                    continue;
+                }
+                if selected_level.is_some() {
+                    return cell_error("Only one level can be selected in a criterion");
+                }
+                selected_level = Some(level_index);
+
+                let pick_score = item.user_pick.score;
+                if self.rubric_type == RubricType::PointRange
+                    && !(pick_score >= item.score && pick_score <= item.to_score)
+                {
+                    return cell_error("Selected score is out of the score range of the level");
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl_jsonb_for_db!(RubricTableData);
 
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, FromPrimitive, ToPrimitive, AsExpression, FromSqlRow, Enum,
+)]
+#[diesel(sql_type = Integer)]
+pub enum RubricShareMode {
+    // Only creator can view and manage the rubric
+    Private,
+    // Teachers of the space can view and duplicate the rubric
+    ReadOnly,
+    // Teachers of the space can edit the rubric
+    Editable,
+}
+
+impl_enum_for_db!(RubricShareMode);
+
+impl Default for RubricShareMode {
+    fn default() -> Self {
+        Self::Private
+    }
+}
+
+impl RubricShareMode {
+    pub fn get_name(&self) -> String {
+        match self {
+            RubricShareMode::Private => "private",
+            RubricShareMode::ReadOnly => "read_only",
+            RubricShareMode::Editable => "editable",
+        }
+        .into()
+    }
+}
+
 #[derive(Debug, Clone, Insertable, Queryable, InputObject, SimpleObject)]
 #[diesel(table_name = rubrics)]
 #[graphql(input_name = "RubricInput")]
@@ -100,6 +289,13 @@ pub struct Rubric {
     pub created_at: i64,
     #[graphql(skip_input)]
     pub user_id: i32,
+    // Rubric owned by a space is shared with teachers of the space by share mode
+    pub space_id: Option<i32>,
+    #[graphql(default)]
+    pub share_mode: RubricShareMode,
+    // Increased each time name or data of the rubric is changed
+    #[graphql(skip_input)]
+    pub version: i32,
 }
 
 impl Rubric {
@@ -114,10 +310,28 @@ impl Rubric {
                 rubrics::updated_at.eq(item.updated_at),
                 rubrics::name.eq(&item.name),
                 rubrics::data.eq(&item.data),
+                rubrics::space_id.eq(item.space_id),
+                rubrics::share_mode.eq(item.share_mode),
+                rubrics::version.eq(item.version),
             ))
             .get_result(conn)
     }
 
+    // New private rubric of the user with the content of this rubric
+    pub fn duplicate(&self, user_id: i32, space_id: Option<i32>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: format!("Copy of {}", self.name),
+            data: self.data.clone(),
+            updated_at: get_now_as_secs(),
+            created_at: get_now_as_secs(),
+            user_id,
+            space_id,
+            share_mode: RubricShareMode::Private,
+            version: 1,
+        }
+    }
+
     pub fn find_by_id(conn: &mut PgConnection, rubric_id: Uuid) -> Result<Self, Error> {
         rubrics::table.find(rubric_id).first(conn)
     }
@@ -128,12 +342,74 @@ impl Rubric {
             .get_results(conn)
     }
 
+    // Rubrics of the space which are shared with the user or created by the user
+    pub fn find_all_by_space(
+        conn: &mut PgConnection,
+        space_id: i32,
+        user_id: i32,
+    ) -> Result<Vec<Self>, Error> {
+        rubrics::table
+            .filter(rubrics::space_id.eq(space_id))
+            .filter(
+                rubrics::user_id
+                    .eq(user_id)
+                    .or(rubrics::share_mode.ne(RubricShareMode::Private)),
+            )
+            .order_by(rubrics::updated_at.desc())
+            .get_results(conn)
+    }
+
     pub fn remove(conn: &mut PgConnection, rubric_id: Uuid) -> Result<(), Error> {
         diesel::delete(rubrics::table.find(rubric_id)).execute(conn)?;
         Ok(())
     }
 }
 
+// Content of a rubric at a version, graded data of rubric submissions are copied from it
+#[derive(Debug, Clone, Insertable, Queryable, SimpleObject)]
+#[diesel(table_name = rubric_versions)]
+pub struct RubricVersion {
+    pub rubric_id: Uuid,
+    pub version: i32,
+    pub name: String,
+    pub data: RubricTableData,
+    pub user_id: i32,
+    pub created_at: i64,
+}
+
+impl RubricVersion {
+    pub fn new(rubric: &Rubric, user_id: i32) -> Self {
+        Self {
+            rubric_id: rubric.id,
+            version: rubric.version,
+            name: rubric.name.clone(),
+            data: rubric.data.clone(),
+            user_id,
+            created_at: get_now_as_secs(),
+        }
+    }
+
+    // Stored versions are never changed
+    pub fn insert(conn: &mut PgConnection, item: Self) -> Result<(), Error> {
+        diesel::insert_into(rubric_versions::table)
+            .values(&item)
+            .on_conflict((rubric_versions::rubric_id, rubric_versions::version))
+            .do_nothing()
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find_all_by_rubric(
+        conn: &mut PgConnection,
+        rubric_id: Uuid,
+    ) -> Result<Vec<Self>, Error> {
+        rubric_versions::table
+            .filter(rubric_versions::rubric_id.eq(rubric_id))
+            .order_by(rubric_versions::version.desc())
+            .get_results(conn)
+    }
+}
+
 #[derive(Debug, Clone, Insertable, Queryable, InputObject, SimpleObject)]
 #[diesel(table_name = rubric_submissions)]
 #[graphql(input_name = "RubricSubmissionInput")]
@@ -145,16 +421,28 @@ pub struct RubricSubmission {
     pub updated_at: i64,
     #[graphql(skip_input)]
     pub created_at: i64,
+    // Version of the rubric which graded data is copied from, later changes of the rubric
+    // don't affect graded data
+    #[graphql(skip_input)]
+    pub rubric_version: Option<i32>,
+    // Draft grading suggested by AI, it's applied to graded data once teacher accepts it
+    #[graphql(skip_input)]
+    pub ai_suggested_data: Option<RubricTableData>,
+    #[graphql(skip_input)]
+    pub ai_suggested_at: Option<i64>,
 }
 
 impl RubricSubmission {
-    pub fn new(submission_id: i32, rubric_id: Uuid, graded_data: RubricTableData) -> Self {
+    pub fn new(submission_id: i32, rubric: Rubric) -> Self {
         Self {
             submission_id,
-            rubric_id: Some(rubric_id),
-            graded_data,
+            rubric_id: Some(rubric.id),
+            graded_data: rubric.data,
             updated_at: get_now_as_secs(),
             created_at: get_now_as_secs(),
+            rubric_version: Some(rubric.version),
+            ai_suggested_data: None,
+            ai_suggested_at: None,
         }
     }
 
@@ -172,6 +460,20 @@ impl RubricSubmission {
             .get_result(conn)
     }
 
+    pub fn update_ai_suggestion(
+        conn: &mut PgConnection,
+        submission_id: i32,
+        ai_suggested_data: Option<RubricTableData>,
+    ) -> Result<Self, Error> {
+        let ai_suggested_at = ai_suggested_data.as_ref().map(|_| get_now_as_secs());
+        diesel::update(rubric_submissions::table.find(submission_id))
+            .set((
+                rubric_submissions::ai_suggested_data.eq(ai_suggested_data),
+                rubric_submissions::ai_suggested_at.eq(ai_suggested_at),
+            ))
+            .get_result(conn)
+    }
+
     pub fn find_by_submission(conn: &mut PgConnection, submission_id: i32) -> Result<Self, Error> {
         rubric_submissions::table.find(submission_id).first(conn)
     }
@@ -187,3 +489,169 @@ impl RubricSubmission {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+
+    use super::*;
+
+    fn item(score: f64, to_score: f64) -> RubricTableItem {
+        RubricTableItem {
+            explanation: String::new(),
+            score,
+            to_score,
+            user_pick: RubricUserPick::default(),
+        }
+    }
+
+    fn pick(mut item: RubricTableItem, score: f64) -> RubricTableItem {
+        item.user_pick = RubricUserPick {
+            selected: true,
+            score,
+            comment: String::new(),
+        };
+        item
+    }
+
+    // 2 criteria x 3 levels
+    fn table(rubric_type: RubricType) -> RubricTableData {
+        RubricTableData {
+            rubric_type,
+            criteria: vec!["Content".into(), "Grammar".into()],
+            weighting_criteria: vec![],
+            level: vec!["Poor".into(), "Good".into(), "Excellent".into()],
+            items: vec![
+                vec![item(0.0, 1.0), item(2.0, 3.0), item(4.0, 5.0)],
+                vec![item(0.0, 1.0), item(1.0, 2.0), item(2.0, 2.0)],
+            ],
+        }
+    }
+
+    fn assert_invalid(
+        data: &RubricTableData,
+        criteria_index: Option<usize>,
+        level_index: Option<usize>,
+    ) {
+        let error = data.validate().unwrap_err();
+        assert_eq!(
+            (error.criteria_index, error.level_index),
+            (criteria_index, level_index),
+            "{}",
+            error.message
+        );
+    }
+
+    #[test]
+    fn validate_valid_tables() {
+        assert!(table(RubricType::PointBased).validate().is_ok());
+        assert!(table(RubricType::PointRange).validate().is_ok());
+
+        let mut picked = table(RubricType::PointRange);
+        picked.items[0][1] = pick(picked.items[0][1].clone(), 2.5);
+        picked.items[1][2] = pick(picked.items[1][2].clone(), 2.0);
+        picked.weighting_criteria = vec![Some(0.5), None];
+        assert!(picked.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_table_shape() {
+        let mut data = table(RubricType::PointBased);
+        data.criteria.clear();
+        assert_invalid(&data, None, None);
+
+        let mut data = table(RubricType::PointBased);
+        data.level.clear();
+        assert_invalid(&data, None, None);
+
+        let mut data = table(RubricType::PointBased);
+        data.items.pop();
+        assert_invalid(&data, None, None);
+
+        let mut data = table(RubricType::PointBased);
+        data.weighting_criteria = vec![Some(1.0)];
+        assert_invalid(&data, None, None);
+
+        let mut data = table(RubricType::PointBased);
+        data.items[1].pop();
+        assert_invalid(&data, Some(1), None);
+    }
+
+    #[test]
+    fn validate_scores() {
+        let mut data = table(RubricType::PointBased);
+        data.weighting_criteria = vec![None, Some(-1.0)];
+        assert_invalid(&data, Some(1), None);
+
+        let mut data = table(RubricType::PointBased);
+        data.weighting_criteria = vec![Some(f64::NAN), None];
+        assert_invalid(&data, Some(0), None);
+
+        let mut data = table(RubricType::PointBased);
+        data.items[0][2].score = -1.0;
+        assert_invalid(&data, Some(0), Some(2));
+
+        let mut data = table(RubricType::PointBased);
+        data.items[1][0].score = f64::INFINITY;
+        assert_invalid(&data, Some(1), Some(0));
+
+        // To score is ignored by point based rubric
+        let mut data = table(RubricType::PointBased);
+        data.items[0][1].to_score = 0.0;
+        assert!(data.validate().is_ok());
+
+        let mut data = table(RubricType::PointRange);
+        data.items[0][1].to_score = 1.0;
+        assert_invalid(&data, Some(0), Some(1));
+    }
+
+    #[test]
+    fn validate_picks() {
+        let mut data = table(RubricType::PointBased);
+        data.items[0][0] = pick(data.items[0][0].clone(), 0.0);
+        data.items[0][2] = pick(data.items[0][2].clone(), 4.0);
+        assert_invalid(&data, Some(0), Some(2));
+
+        let mut data = table(RubricType::PointRange);
+        data.items[1][1] = pick(data.items[1][1].clone(), 2.5);
+        assert_invalid(&data, Some(1), Some(1));
+
+        let mut data = table(RubricType::PointRange);
+        data.items[1][1] = pick(data.items[1][1].clone(), 0.5);
+        assert_invalid(&data, Some(1), Some(1));
+
+        let mut data = table(RubricType::PointRange);
+        data.items[1][1] = pick(data.items[1][1].clone(), f64::NAN);
+        assert_invalid(&data, Some(1), Some(1));
+    }
+
+    #[test]
+    fn max_rubric_score_uses_best_level() {
+        assert_eq!(table(RubricType::PointBased).max_rubric_score(), 6.0);
+        assert_eq!(table(RubricType::PointRange).max_rubric_score(), 7.0);
+
+        let mut weighted = table(RubricType::PointRange);
+        weighted.weighting_criteria = vec![Some(0.5), None];
+        assert_eq!(weighted.max_rubric_score(), 4.5);
+        assert_eq!(RubricTableData::default().max_rubric_score(), 0.0);
+    }
+
+    #[test]
+    fn total_rubric_score_of_picks() {
+        let mut data = table(RubricType::PointBased);
+        assert_eq!(data.total_rubric_score(), 0.0);
+
+        // Score of the pick is ignored by point based rubric
+        data.items[0][2] = pick(data.items[0][2].clone(), 100.0);
+        data.items[1][1] = pick(data.items[1][1].clone(), 0.0);
+        assert_eq!(data.total_rubric_score(), 5.0);
+        data.weighting_criteria = vec![Some(0.5), Some(2.0)];
+        assert_eq!(data.total_rubric_score(), 4.0);
+
+        let mut data = table(RubricType::PointRange);
+        data.items[0][2] = pick(data.items[0][2].clone(), 4.5);
+        data.items[1][0] = pick(data.items[1][0].clone(), 1.0);
+        assert_eq!(data.total_rubric_score(), 5.5);
+    }
+}
@@ -13,6 +13,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    assignment_accommodations (assignment_id, user_id) {
+        assignment_id -> Int4,
+        user_id -> Int4,
+        test_duration -> Nullable<Int4>,
+        max_number_of_attempt -> Nullable<Int4>,
+        due_at -> Nullable<Int8>,
+        close_at -> Nullable<Int8>,
+        updated_at -> Int8,
+        created_at -> Int8,
+    }
+}
+
+diesel::table! {
+    assignment_regrades (id) {
+        id -> Uuid,
+        assignment_id -> Int4,
+        quiz_id -> Uuid,
+        creator_id -> Int4,
+        status -> Int4,
+        summary -> Jsonb,
+        updated_at -> Int8,
+        created_at -> Int8,
+    }
+}
+
 diesel::table! {
     assignment_submissions (id) {
         id -> Int4,
@@ -31,6 +57,11 @@ diesel::table! {
         submit_at -> Nullable<Int8>,
         allow_rework -> Bool,
         test_duration -> Nullable<Int4>,
+        random_seed -> Nullable<Int8>,
+        is_late -> Bool,
+        deadline_at -> Nullable<Int8>,
+        paused_at -> Nullable<Int8>,
+        needs_grading -> Bool,
     }
 }
 
@@ -46,6 +77,39 @@ diesel::table! {
         band_score_id -> Nullable<Int4>,
         grade_method -> Int4,
         grade_by_rubric_id -> Nullable<Uuid>,
+        negative_marking -> Nullable<Float8>,
+        max_score -> Nullable<Float8>,
+        question_pools -> Jsonb,
+        shuffle_options -> Bool,
+        available_from -> Nullable<Int8>,
+        due_at -> Nullable<Int8>,
+        close_at -> Nullable<Int8>,
+        late_penalty -> Nullable<Float8>,
+        grade_policy -> Int4,
+    }
+}
+
+diesel::table! {
+    bank_quiz_tags (bank_quiz_id, tag) {
+        bank_quiz_id -> Uuid,
+        tag -> Varchar,
+        created_at -> Int8,
+    }
+}
+
+diesel::table! {
+    bank_quizzes (id) {
+        id -> Uuid,
+        space_id -> Int4,
+        creator_id -> Int4,
+        original_quiz_id -> Nullable<Uuid>,
+        quiz_type -> Int4,
+        question_data -> Jsonb,
+        answer_data -> Jsonb,
+        points -> Float8,
+        difficulty -> Int4,
+        updated_at -> Int8,
+        created_at -> Int8,
     }
 }
 
@@ -56,6 +120,7 @@ diesel::table! {
         range -> Jsonb,
         updated_at -> Int8,
         created_at -> Int8,
+        space_id -> Nullable<Int4>,
     }
 }
 
@@ -151,6 +216,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    page_content_versions (id) {
+        id -> Uuid,
+        page_content_id -> Uuid,
+        body -> Jsonb,
+        quiz_data -> Jsonb,
+        name -> Nullable<Text>,
+        is_auto -> Bool,
+        creator_id -> Int4,
+        created_at -> Int8,
+    }
+}
+
 diesel::table! {
     page_contents (id) {
         id -> Uuid,
@@ -187,6 +265,8 @@ diesel::table! {
         answer_data -> Jsonb,
         updated_at -> Int8,
         created_at -> Int8,
+        points -> Float8,
+        bank_quiz_id -> Nullable<Uuid>,
     }
 }
 
@@ -198,6 +278,9 @@ diesel::table! {
         score -> Float8,
         updated_at -> Int8,
         created_at -> Int8,
+        manual_score -> Nullable<Float8>,
+        comment -> Nullable<Text>,
+        graded_at -> Nullable<Int8>,
     }
 }
 
@@ -208,6 +291,20 @@ diesel::table! {
         graded_data -> Jsonb,
         updated_at -> Int8,
         created_at -> Int8,
+        rubric_version -> Nullable<Int4>,
+        ai_suggested_data -> Nullable<Jsonb>,
+        ai_suggested_at -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    rubric_versions (rubric_id, version) {
+        rubric_id -> Uuid,
+        version -> Int4,
+        name -> Text,
+        data -> Jsonb,
+        user_id -> Int4,
+        created_at -> Int8,
     }
 }
 
@@ -219,6 +316,9 @@ diesel::table! {
         updated_at -> Int8,
         created_at -> Int8,
         user_id -> Int4,
+        space_id -> Nullable<Int4>,
+        share_mode -> Int4,
+        version -> Int4,
     }
 }
 
@@ -279,10 +379,19 @@ diesel::table! {
 }
 
 diesel::joinable!(ai_history_sessions -> users (user_id));
+diesel::joinable!(assignment_accommodations -> assignments (assignment_id));
+diesel::joinable!(assignment_accommodations -> users (user_id));
+diesel::joinable!(assignment_regrades -> assignments (assignment_id));
+diesel::joinable!(assignment_regrades -> quiz_blocks (quiz_id));
+diesel::joinable!(assignment_regrades -> users (creator_id));
 diesel::joinable!(assignment_submissions -> assignments (assignment_id));
 diesel::joinable!(assignment_submissions -> documents (document_id));
 diesel::joinable!(assignment_submissions -> users (user_id));
 diesel::joinable!(assignments -> band_scores (band_score_id));
+diesel::joinable!(band_scores -> spaces (space_id));
+diesel::joinable!(bank_quiz_tags -> bank_quizzes (bank_quiz_id));
+diesel::joinable!(bank_quizzes -> spaces (space_id));
+diesel::joinable!(bank_quizzes -> users (creator_id));
 diesel::joinable!(assignments -> documents (document_id));
 diesel::joinable!(assignments -> rubrics (grade_by_rubric_id));
 diesel::joinable!(document_assigned_users -> documents (document_id));
@@ -296,6 +405,8 @@ diesel::joinable!(embedded_form_responses -> users (response_user_id));
 diesel::joinable!(embedded_sessions -> documents (document_id));
 diesel::joinable!(notification_receivers -> notifications (notification_id));
 diesel::joinable!(notification_receivers -> users (user_id));
+diesel::joinable!(page_content_versions -> page_contents (page_content_id));
+diesel::joinable!(page_content_versions -> users (creator_id));
 diesel::joinable!(page_contents -> pages (page_id));
 diesel::joinable!(pages -> documents (document_id));
 diesel::joinable!(pages -> users (created_by_id));
@@ -305,6 +416,9 @@ diesel::joinable!(quiz_user_answer -> quiz_blocks (quiz_id));
 diesel::joinable!(quiz_user_answer -> users (user_id));
 diesel::joinable!(rubric_submissions -> assignment_submissions (submission_id));
 diesel::joinable!(rubric_submissions -> rubrics (rubric_id));
+diesel::joinable!(rubric_versions -> rubrics (rubric_id));
+diesel::joinable!(rubric_versions -> users (user_id));
+diesel::joinable!(rubrics -> spaces (space_id));
 diesel::joinable!(rubrics -> users (user_id));
 diesel::joinable!(space_invite_tokens -> spaces (space_id));
 diesel::joinable!(space_invite_tokens -> users (creator_id));
@@ -317,9 +431,13 @@ diesel::joinable!(user_activities -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     ai_history_sessions,
+    assignment_accommodations,
+    assignment_regrades,
     assignment_submissions,
     assignments,
     band_scores,
+    bank_quiz_tags,
+    bank_quizzes,
     document_assigned_users,
     document_tags,
     documents,
@@ -328,11 +446,13 @@ diesel::allow_tables_to_appear_in_same_query!(
     files,
     notification_receivers,
     notifications,
+    page_content_versions,
     page_contents,
     pages,
     quiz_blocks,
     quiz_user_answer,
     rubric_submissions,
+    rubric_versions,
     rubrics,
     space_invite_tokens,
     space_members,
@@ -3,6 +3,7 @@ use diesel::{AsChangeset, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl
 use uuid::Uuid;
 
 use super::schema::assignment_submissions;
+use super::GradePolicy;
 use crate::util::get_now_as_secs;
 
 #[derive(Debug, Clone, Insertable)]
@@ -20,6 +21,9 @@ pub struct NewSubmission {
     pub allow_rework: bool,
     pub submit_at: Option<i64>,
     pub test_duration: Option<i32>,
+    pub random_seed: Option<i64>,
+    pub is_late: bool,
+    pub deadline_at: Option<i64>,
 }
 
 impl From<Submission> for NewSubmission {
@@ -37,6 +41,9 @@ impl From<Submission> for NewSubmission {
             allow_rework: value.allow_rework,
             submit_at: value.submit_at,
             test_duration: value.test_duration,
+            random_seed: value.random_seed,
+            is_late: value.is_late,
+            deadline_at: value.deadline_at,
         }
     }
 }
@@ -54,6 +61,7 @@ impl NewSubmission {
         attempt_number: i32,
         allow_rework: bool,
         test_duration: Option<i32>,
+        random_seed: Option<i64>,
     ) -> Self {
         Self {
             assignment_id,
@@ -68,6 +76,10 @@ impl NewSubmission {
             attempt_number,
             allow_rework,
             test_duration,
+            random_seed,
+            is_late: false,
+            deadline_at: test_duration
+                .map(|test_duration| get_now_as_secs() + test_duration as i64),
         }
     }
 }
@@ -85,6 +97,18 @@ pub struct GradeSubmissionData {
     pub updated_at: i64,
 }
 
+// Overall grade of a student for an assignment, resolved by the grade policy
+#[derive(Debug, Clone, SimpleObject)]
+pub struct AssignmentGrade {
+    pub assignment_id: i32,
+    pub user_id: i32,
+    pub grade_policy: GradePolicy,
+    pub grade: Option<f64>,
+    // Submission deciding the grade, none for average policy
+    pub submission_id: Option<i32>,
+    pub number_of_graded_attempts: i32,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
 pub enum SubmissionStatus {
     InDoing,
@@ -113,6 +137,15 @@ pub struct Submission {
     pub submit_at: Option<i64>,
     pub allow_rework: bool,
     pub test_duration: Option<i32>,
+    // Seed of randomization of the submission document, e.g. quizzes drawn from question pools
+    #[graphql(skip)]
+    pub random_seed: Option<i64>,
+    pub is_late: bool,
+    // Time the test is auto submitted, it moves when teacher extends or pauses the test
+    pub deadline_at: Option<i64>,
+    pub paused_at: Option<i64>,
+    // Some answers of writing blocks are not scored by teacher yet
+    pub needs_grading: bool,
 }
 
 impl Submission {
@@ -197,12 +230,28 @@ impl Submission {
             .get_result(conn)
     }
 
+    pub fn update_deadline(
+        conn: &mut PgConnection,
+        submission_id: i32,
+        deadline_at: Option<i64>,
+        paused_at: Option<i64>,
+    ) -> Result<Self, Error> {
+        diesel::update(assignment_submissions::table.find(submission_id))
+            .set((
+                assignment_submissions::deadline_at.eq(deadline_at),
+                assignment_submissions::paused_at.eq(paused_at),
+                assignment_submissions::updated_at.eq(get_now_as_secs()),
+            ))
+            .get_result(conn)
+    }
+
     pub fn submit(
         conn: &mut PgConnection,
         submission_id: i32,
         grade: f64,
         final_grade: f64,
         is_auto_grade: bool,
+        is_late: bool,
     ) -> Result<(), Error> {
         let (feedback_at, feedback) = if is_auto_grade {
             (Some(get_now_as_secs()), Some("Auto feedback"))
@@ -219,7 +268,35 @@ impl Submission {
                 assignment_submissions::allow_for_student_view_answer.eq(is_auto_grade),
                 assignment_submissions::feedback_at.eq(feedback_at),
                 assignment_submissions::feedback.eq(feedback),
+                assignment_submissions::is_late.eq(is_late),
+                assignment_submissions::paused_at.eq(None::<i64>),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn update_grades(
+        conn: &mut PgConnection,
+        submission_id: i32,
+        auto_grade: f64,
+        final_grade: Option<f64>,
+    ) -> Result<Self, Error> {
+        diesel::update(assignment_submissions::table.find(submission_id))
+            .set((
+                assignment_submissions::auto_grade.eq(auto_grade),
+                assignment_submissions::final_grade.eq(final_grade),
+                assignment_submissions::updated_at.eq(get_now_as_secs()),
             ))
+            .get_result(conn)
+    }
+
+    pub fn update_needs_grading(
+        conn: &mut PgConnection,
+        submission_id: i32,
+        needs_grading: bool,
+    ) -> Result<(), Error> {
+        diesel::update(assignment_submissions::table.find(submission_id))
+            .set(assignment_submissions::needs_grading.eq(needs_grading))
             .execute(conn)?;
         Ok(())
     }
@@ -6,6 +6,8 @@ use hmac::crypto_mac::InvalidKeyLength;
 use jsonwebtoken::errors::Error as JWTError;
 use lettre::address::AddressError;
 
+use crate::db::RubricValidationError;
+
 #[derive(Debug, Error, Clone)]
 #[error(transparent)]
 pub enum IkigaiError {
@@ -57,6 +59,20 @@ impl ErrorExtensions for IkigaiError {
     }
 }
 
+impl ErrorExtensions for RubricValidationError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(&self.message).extend_with(|_, e| {
+            e.set("code", 400);
+            if let Some(criteria_index) = self.criteria_index {
+                e.set("criteriaIndex", criteria_index);
+            }
+            if let Some(level_index) = self.level_index {
+                e.set("levelIndex", level_index);
+            }
+        })
+    }
+}
+
 pub trait IkigaiErrorExt<T>
 where
     Self: Sized,
@@ -196,6 +212,20 @@ impl From<std::io::Error> for IkigaiError {
     }
 }
 
+impl From<csv::Error> for IkigaiError {
+    fn from(e: csv::Error) -> Self {
+        error!("CSV Error: {:?}", e);
+        Self::InternalServerError
+    }
+}
+
+impl From<rust_xlsxwriter::XlsxError> for IkigaiError {
+    fn from(e: rust_xlsxwriter::XlsxError) -> Self {
+        error!("XLSX Error: {:?}", e);
+        Self::InternalServerError
+    }
+}
+
 impl From<JobBuilderError> for IkigaiError {
     fn from(e: JobBuilderError) -> Self {
         error!("Builder Error: {:?}", e);
@@ -1,11 +1,15 @@
 use async_graphql::*;
 use diesel::Connection;
+use uuid::Uuid;
 
-use crate::authorization::DocumentActionPermission;
+use crate::authorization::{DocumentActionPermission, SpaceActionPermission};
 use crate::db::*;
 use crate::error::{IkigaiError, IkigaiErrorExt};
+use crate::graphql::validator::{AssignmentAccommodationValidator, AssignmentSettingValidator};
 use crate::helper::*;
 use crate::notification_center::send_notification;
+use crate::service::ikigai_ai::IkigaiAI;
+use crate::util::get_now_as_secs;
 
 #[derive(Default)]
 pub struct AssignmentMutation;
@@ -16,7 +20,7 @@ impl AssignmentMutation {
         &self,
         ctx: &Context<'_>,
         assignment_id: i32,
-        data: UpdateAssignmentData,
+        #[graphql(validator(custom = "AssignmentSettingValidator"))] data: UpdateAssignmentData,
     ) -> Result<bool> {
         let mut conn = get_conn_from_ctx(ctx).await?;
         let assignment = Assignment::find_by_id(&mut conn, assignment_id).format_err()?;
@@ -26,8 +30,308 @@ impl AssignmentMutation {
             DocumentActionPermission::ManageDocument,
         )
         .await?;
-        Assignment::update(&mut conn, assignment_id, data).format_err()?;
 
+        if let Some(band_score_id) = data.band_score_id {
+            let band_score = BandScore::find(&mut conn, band_score_id).format_err()?;
+            if let Some(band_score_space_id) = band_score.space_id {
+                let document =
+                    Document::find_by_id(&mut conn, assignment.document_id).format_err()?;
+                if document.space_id != Some(band_score_space_id) {
+                    return Err(IkigaiError::new_bad_request(
+                        "Band score doesn't belong to this space",
+                    ))
+                    .format_err();
+                }
+            }
+        }
+
+        let updated_assignment = Assignment::update(&mut conn, assignment_id, data).format_err()?;
+        if updated_assignment.close_at != assignment.close_at {
+            match updated_assignment.close_at {
+                Some(close_at) => {
+                    schedule_close_assignment(assignment_id, close_at, None).format_err()?
+                }
+                None => cancel_close_assignment(assignment_id, None),
+            }
+        }
+
+        Ok(true)
+    }
+
+    // Re-grade submissions in background after the answer key of the quiz is changed
+    async fn assignment_regrade_quiz(
+        &self,
+        ctx: &Context<'_>,
+        assignment_id: i32,
+        quiz_id: Uuid,
+    ) -> Result<AssignmentRegrade> {
+        let assignment = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            Assignment::find_by_id(&mut conn, assignment_id).format_err()?
+        };
+        document_quick_authorize(
+            ctx,
+            assignment.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await?;
+
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let is_assignment_quiz = find_document_quizzes(&mut conn, assignment.document_id)
+            .format_err()?
+            .iter()
+            .any(|quiz| quiz.id == quiz_id);
+        if !is_assignment_quiz {
+            return Err(IkigaiError::new_bad_request(
+                "Quiz doesn't belong to this assignment",
+            ))
+            .format_err();
+        }
+
+        let regrade = AssignmentRegrade::insert(
+            &mut conn,
+            AssignmentRegrade::new(assignment.id, quiz_id, user_id),
+        )
+        .format_err()?;
+        add_regrade_quiz_job(&regrade).format_err()?;
+
+        Ok(regrade)
+    }
+
+    async fn assignment_update_question_pools(
+        &self,
+        ctx: &Context<'_>,
+        assignment_id: i32,
+        question_pools: QuestionPools,
+    ) -> Result<Assignment> {
+        let assignment = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            Assignment::find_by_id(&mut conn, assignment_id).format_err()?
+        };
+        document_quick_authorize(
+            ctx,
+            assignment.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let quiz_ids: Vec<Uuid> = find_document_quizzes(&mut conn, assignment.document_id)
+            .format_err()?
+            .into_iter()
+            .map(|quiz| quiz.id)
+            .collect();
+        let mut pooled_quiz_ids: Vec<Uuid> = vec![];
+        for pool in question_pools.items.iter() {
+            if pool.draw_count < 1 || pool.draw_count as usize > pool.quiz_ids.len() {
+                return Err(IkigaiError::new_bad_request(
+                    "Draw count must be between 1 and number of quizzes in the pool",
+                ))
+                .format_err();
+            }
+
+            for quiz_id in pool.quiz_ids.iter() {
+                if !quiz_ids.contains(quiz_id) {
+                    return Err(IkigaiError::new_bad_request(
+                        "Quiz of the pool doesn't belong to this assignment",
+                    ))
+                    .format_err();
+                }
+                if pooled_quiz_ids.contains(quiz_id) {
+                    return Err(IkigaiError::new_bad_request(
+                        "A quiz can only belong to one pool",
+                    ))
+                    .format_err();
+                }
+                pooled_quiz_ids.push(*quiz_id);
+            }
+        }
+
+        Assignment::update_question_pools(&mut conn, assignment_id, &question_pools)
+            .format_err()?;
+        let assignment = refresh_assignment_max_score(&mut conn, assignment.document_id)
+            .format_err()?
+            .unwrap_or(assignment);
+        Ok(assignment)
+    }
+
+    async fn assignment_upsert_accommodation(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(custom = "AssignmentAccommodationValidator"))]
+        data: AssignmentAccommodation,
+    ) -> Result<AssignmentAccommodation> {
+        let assignment = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            Assignment::find_by_id(&mut conn, data.assignment_id).format_err()?
+        };
+        document_quick_authorize(
+            ctx,
+            assignment.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let document = Document::find_by_id(&mut conn, assignment.document_id).format_err()?;
+        let member = match document.space_id {
+            Some(space_id) => {
+                SpaceMember::find_opt(&mut conn, space_id, data.user_id).format_err()?
+            }
+            None => None,
+        };
+        if member.map_or(true, |member| member.role != Role::Student) {
+            return Err(IkigaiError::new_bad_request(
+                "Accommodation can only be given to a student of the space",
+            ))
+            .format_err();
+        }
+
+        let accommodation = AssignmentAccommodation::upsert(&mut conn, data).format_err()?;
+        schedule_close_student_assignment(&mut conn, &assignment, accommodation.user_id)
+            .format_err()?;
+
+        Ok(accommodation)
+    }
+
+    async fn assignment_remove_accommodation(
+        &self,
+        ctx: &Context<'_>,
+        assignment_id: i32,
+        user_id: i32,
+    ) -> Result<bool> {
+        let assignment = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            Assignment::find_by_id(&mut conn, assignment_id).format_err()?
+        };
+        document_quick_authorize(
+            ctx,
+            assignment.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        AssignmentAccommodation::remove(&mut conn, assignment_id, user_id).format_err()?;
+        // The student may have missed the assignment wide close job by a later close time
+        schedule_close_student_assignment(&mut conn, &assignment, user_id).format_err()?;
+        Ok(true)
+    }
+
+    async fn assignment_extend_submission_time(
+        &self,
+        ctx: &Context<'_>,
+        assignment_id: i32,
+        // All running tests of the assignment in case of empty
+        submission_ids: Option<Vec<i32>>,
+        extra_seconds: i32,
+    ) -> Result<Vec<Submission>> {
+        let submissions = get_running_submissions(ctx, assignment_id, submission_ids).await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let mut res = vec![];
+        for submission in submissions {
+            res.push(
+                extend_submission_time(&mut conn, &submission, extra_seconds as i64)
+                    .format_err()?,
+            );
+        }
+
+        Ok(res)
+    }
+
+    async fn assignment_pause_submissions(
+        &self,
+        ctx: &Context<'_>,
+        assignment_id: i32,
+        submission_ids: Option<Vec<i32>>,
+    ) -> Result<Vec<Submission>> {
+        let submissions = get_running_submissions(ctx, assignment_id, submission_ids).await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let mut res = vec![];
+        for submission in submissions {
+            res.push(pause_submission(&mut conn, &submission).format_err()?);
+        }
+
+        Ok(res)
+    }
+
+    async fn assignment_resume_submissions(
+        &self,
+        ctx: &Context<'_>,
+        assignment_id: i32,
+        submission_ids: Option<Vec<i32>>,
+    ) -> Result<Vec<Submission>> {
+        let submissions = get_running_submissions(ctx, assignment_id, submission_ids).await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let mut res = vec![];
+        for submission in submissions {
+            res.push(resume_submission(&mut conn, &submission).format_err()?);
+        }
+
+        Ok(res)
+    }
+
+    async fn assignment_create_band_score(
+        &self,
+        ctx: &Context<'_>,
+        space_id: i32,
+        mut data: NewBandScore,
+    ) -> Result<BandScore> {
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+
+        data.space_id = Some(space_id);
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let band_score = BandScore::insert(&mut conn, data).format_err()?;
+        Ok(band_score)
+    }
+
+    async fn assignment_update_band_score(
+        &self,
+        ctx: &Context<'_>,
+        band_score_id: i32,
+        data: NewBandScore,
+    ) -> Result<BandScore> {
+        let band_score = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            BandScore::find(&mut conn, band_score_id).format_err()?
+        };
+        let Some(space_id) = band_score.space_id else {
+            return Err(IkigaiError::new_bad_request(
+                "Cannot update default band score",
+            ))
+            .format_err();
+        };
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let band_score =
+            BandScore::update(&mut conn, band_score_id, data.name, data.range).format_err()?;
+        Ok(band_score)
+    }
+
+    async fn assignment_remove_band_score(
+        &self,
+        ctx: &Context<'_>,
+        band_score_id: i32,
+    ) -> Result<bool> {
+        let band_score = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            BandScore::find(&mut conn, band_score_id).format_err()?
+        };
+        let Some(space_id) = band_score.space_id else {
+            return Err(IkigaiError::new_bad_request(
+                "Cannot remove default band score",
+            ))
+            .format_err();
+        };
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        BandScore::remove(&mut conn, band_score_id).format_err()?;
         Ok(true)
     }
 
@@ -89,6 +393,18 @@ impl AssignmentMutation {
             DocumentActionPermission::ViewDocument,
         )
         .await?;
+        let assignment = get_student_assignment(&mut conn, assignment, user_id).format_err()?;
+
+        let now = get_now_as_secs();
+        if !assignment.is_available(now) {
+            return Err(IkigaiError::new_bad_request(
+                "This assignment is not available yet!",
+            ))
+            .format_err();
+        }
+        if assignment.is_closed(now) {
+            return Err(IkigaiError::new_bad_request("This assignment is closed!")).format_err();
+        }
 
         // Check attempt time
         let last_submission =
@@ -144,10 +460,15 @@ impl AssignmentMutation {
             DocumentActionPermission::ViewDocument,
         )
         .await?;
+        let assignment =
+            get_student_assignment(&mut conn, assignment, submission.user_id).format_err()?;
 
         if submission.submit_at.is_some() {
             return Err(IkigaiError::new_bad_request("Cannot submit twice")).format_err()?;
         }
+        if assignment.is_closed(get_now_as_secs()) {
+            return Err(IkigaiError::new_bad_request("This assignment is closed!")).format_err();
+        }
 
         submit_submission(&mut conn, &submission, &assignment, false).format_err()?;
 
@@ -211,6 +532,7 @@ impl AssignmentMutation {
         ctx: &Context<'_>,
         data: RubricSubmission,
     ) -> Result<RubricSubmission, Error> {
+        data.graded_data.validate().map_err(|e| e.extend())?;
         let mut conn = get_conn_from_ctx(ctx).await?;
         let submission = Submission::find_by_id(&mut conn, data.submission_id).format_err()?;
         let assignment =
@@ -233,4 +555,178 @@ impl AssignmentMutation {
 
         Ok(item)
     }
+
+    async fn assignment_suggest_rubric_grade(
+        &self,
+        ctx: &Context<'_>,
+        submission_id: i32,
+        quiz_id: Uuid,
+    ) -> Result<RubricSubmission> {
+        let user = get_user_from_ctx(ctx).await?;
+        let (submission, assignment) = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            let submission = Submission::find_by_id(&mut conn, submission_id).format_err()?;
+            let assignment =
+                Assignment::find_by_id(&mut conn, submission.assignment_id).format_err()?;
+            (submission, assignment)
+        };
+        document_quick_authorize(
+            ctx,
+            assignment.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await?;
+
+        let request_data = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            check_ai_usage(&mut conn, &user).format_err()?;
+            let rubric_submission =
+                RubricSubmission::find_by_submission_opt(&mut conn, submission.id).format_err()?;
+            let rubric_data = match (rubric_submission, assignment.grade_by_rubric_id) {
+                (Some(rubric_submission), _) => rubric_submission.graded_data,
+                (None, Some(rubric_id)) => {
+                    let rubric = Rubric::find_by_id(&mut conn, rubric_id).format_err()?;
+                    RubricSubmission::upsert(
+                        &mut conn,
+                        RubricSubmission::new(submission.id, rubric),
+                    )
+                    .format_err()?
+                    .graded_data
+                }
+                (None, None) => {
+                    return Err(IkigaiError::new_bad_request(
+                        "Assignment is not graded by rubric",
+                    ))
+                    .format_err()
+                }
+            };
+            get_rubric_grading_request(&mut conn, &assignment, &submission, quiz_id, rubric_data)
+                .format_err()?
+        };
+
+        let res = IkigaiAI::grade_by_rubric(&request_data)
+            .await
+            .format_err()?;
+        let suggested_data = res.to_graded_data(&request_data.rubric);
+        suggested_data.validate().map_err(|e| e.extend())?;
+
+        let ai_history = AIHistorySessionBuilder::default()
+            .user_id(user.id)
+            .action(AIAction::GradeByRubric)
+            .request_data(serde_json::to_value(&request_data).unwrap_or_default())
+            .response_data(serde_json::to_value(&res).unwrap_or_default())
+            .build()?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let item = conn
+            .transaction::<_, IkigaiError, _>(|conn| {
+                AIHistorySession::insert(conn, ai_history)?;
+                let item = RubricSubmission::update_ai_suggestion(
+                    conn,
+                    submission.id,
+                    Some(suggested_data),
+                )?;
+                Ok(item)
+            })
+            .format_err()?;
+
+        Ok(item)
+    }
+
+    async fn assignment_accept_rubric_suggestion(
+        &self,
+        ctx: &Context<'_>,
+        submission_id: i32,
+    ) -> Result<RubricSubmission> {
+        let rubric_submission = get_rubric_submission_for_grading(ctx, submission_id).await?;
+        let Some(suggested_data) = rubric_submission.ai_suggested_data.clone() else {
+            return Err(IkigaiError::new_bad_request(
+                "There is no suggestion of this submission",
+            ))
+            .format_err();
+        };
+        suggested_data.validate().map_err(|e| e.extend())?;
+
+        let final_grade = suggested_data.total_rubric_score();
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let item = conn
+            .transaction::<_, IkigaiError, _>(|conn| {
+                Submission::update_final_grade(conn, submission_id, final_grade)?;
+                RubricSubmission::upsert(
+                    conn,
+                    RubricSubmission {
+                        graded_data: suggested_data,
+                        ..rubric_submission
+                    },
+                )?;
+                let item = RubricSubmission::update_ai_suggestion(conn, submission_id, None)?;
+                Ok(item)
+            })
+            .format_err()?;
+
+        Ok(item)
+    }
+
+    async fn assignment_discard_rubric_suggestion(
+        &self,
+        ctx: &Context<'_>,
+        submission_id: i32,
+    ) -> Result<RubricSubmission> {
+        get_rubric_submission_for_grading(ctx, submission_id).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        RubricSubmission::update_ai_suggestion(&mut conn, submission_id, None).format_err()
+    }
+}
+
+// Rubric grading of the submission, teacher only
+async fn get_rubric_submission_for_grading(
+    ctx: &Context<'_>,
+    submission_id: i32,
+) -> Result<RubricSubmission> {
+    let (assignment, rubric_submission) = {
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let submission = Submission::find_by_id(&mut conn, submission_id).format_err()?;
+        let assignment =
+            Assignment::find_by_id(&mut conn, submission.assignment_id).format_err()?;
+        let rubric_submission =
+            RubricSubmission::find_by_submission(&mut conn, submission_id).format_err()?;
+        (assignment, rubric_submission)
+    };
+    document_quick_authorize(
+        ctx,
+        assignment.document_id,
+        DocumentActionPermission::ManageDocument,
+    )
+    .await?;
+
+    Ok(rubric_submission)
+}
+
+// In-progress timed submissions of the assignment, teacher only
+async fn get_running_submissions(
+    ctx: &Context<'_>,
+    assignment_id: i32,
+    submission_ids: Option<Vec<i32>>,
+) -> Result<Vec<Submission>> {
+    let assignment = {
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        Assignment::find_by_id(&mut conn, assignment_id).format_err()?
+    };
+    document_quick_authorize(
+        ctx,
+        assignment.document_id,
+        DocumentActionPermission::ManageDocument,
+    )
+    .await?;
+
+    let mut conn = get_conn_from_ctx(ctx).await?;
+    let submissions = Submission::find_all_by_assignment(&mut conn, assignment_id).format_err()?;
+    Ok(submissions
+        .into_iter()
+        .filter(|submission| submission.submit_at.is_none() && submission.deadline_at.is_some())
+        .filter(|submission| {
+            submission_ids.as_ref().map_or(true, |submission_ids| {
+                submission_ids.contains(&submission.id)
+            })
+        })
+        .collect())
 }
@@ -2,7 +2,8 @@ use crate::authorization::{DocumentActionPermission, SpaceActionPermission};
 use async_graphql::*;
 
 use crate::db::*;
-use crate::error::IkigaiErrorExt;
+use crate::error::{IkigaiError, IkigaiErrorExt};
+use crate::graphql::validator::BandScoreRangesValidator;
 use crate::helper::*;
 
 #[derive(Default)]
@@ -10,12 +11,119 @@ pub struct AssignmentQuery;
 
 #[Object]
 impl AssignmentQuery {
-    async fn assignment_get_band_scores(&self, ctx: &Context<'_>) -> Result<Vec<BandScore>> {
+    async fn assignment_get_band_scores(
+        &self,
+        ctx: &Context<'_>,
+        space_id: Option<i32>,
+    ) -> Result<Vec<BandScore>> {
+        if let Some(space_id) = space_id {
+            space_quick_authorize(ctx, space_id, SpaceActionPermission::ViewSpaceContent).await?;
+        }
+
         let mut conn = get_conn_from_ctx(ctx).await?;
-        let band_scores = BandScore::find_all(&mut conn).format_err()?;
+        let band_scores = if let Some(space_id) = space_id {
+            BandScore::find_all_by_space(&mut conn, space_id).format_err()?
+        } else {
+            BandScore::find_all(&mut conn)
+                .format_err()?
+                .into_iter()
+                .filter(|band_score| band_score.space_id.is_none())
+                .collect()
+        };
         Ok(band_scores)
     }
 
+    async fn assignment_preview_band_score(
+        &self,
+        #[graphql(validator(custom = "BandScoreRangesValidator"))] range: BandScoreRanges,
+        raw_score: f64,
+        max_score: Option<f64>,
+    ) -> f64 {
+        range.find_score(raw_score, max_score.unwrap_or_default())
+    }
+
+    async fn assignment_grade(
+        &self,
+        ctx: &Context<'_>,
+        assignment_id: i32,
+        // Current user in case of empty
+        user_id: Option<i32>,
+    ) -> Result<AssignmentGrade> {
+        let assignment = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            Assignment::find_by_id(&mut conn, assignment_id).format_err()?
+        };
+        let is_teacher = document_quick_authorize(
+            ctx,
+            assignment.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await
+        .is_ok();
+
+        let current_user_id = get_user_id_from_ctx(ctx).await?;
+        let user_id = user_id.unwrap_or(current_user_id);
+        if !is_teacher {
+            if user_id != current_user_id {
+                return Err(IkigaiError::new_bad_request(
+                    "Cannot view grade of other students",
+                ))
+                .format_err();
+            }
+            document_quick_authorize(
+                ctx,
+                assignment.document_id,
+                DocumentActionPermission::ViewDocument,
+            )
+            .await?;
+        }
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let grade =
+            get_assignment_grade(&mut conn, &assignment, user_id, !is_teacher).format_err()?;
+        Ok(grade)
+    }
+
+    async fn assignment_quiz_statistics(
+        &self,
+        ctx: &Context<'_>,
+        assignment_id: i32,
+    ) -> Result<Vec<QuizStatistic>> {
+        let assignment = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            Assignment::find_by_id(&mut conn, assignment_id).format_err()?
+        };
+        document_quick_authorize(
+            ctx,
+            assignment.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        get_assignment_quiz_statistics(&mut conn, &assignment).format_err()
+    }
+
+    async fn assignment_regrades(
+        &self,
+        ctx: &Context<'_>,
+        assignment_id: i32,
+    ) -> Result<Vec<AssignmentRegrade>> {
+        let assignment = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            Assignment::find_by_id(&mut conn, assignment_id).format_err()?
+        };
+        document_quick_authorize(
+            ctx,
+            assignment.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        AssignmentRegrade::find_all_by_assignment(&mut conn, assignment.id).format_err()
+    }
+
     async fn assignment_get_submissions(
         &self,
         ctx: &Context<'_>,
@@ -17,9 +17,11 @@ use crate::graphql::data_loader::{
     SubmissionByAssignmentId,
 };
 use crate::helper::{
-    document_quick_authorize, get_conn_from_ctx, get_public_user_from_loader,
-    get_user_auth_from_ctx, get_user_id_from_ctx,
+    document_quick_authorize, get_assignment_max_score, get_conn_from_ctx,
+    get_public_user_from_loader, get_submission_max_score, get_user_auth_from_ctx,
+    get_user_id_from_ctx,
 };
+use crate::util::get_now_as_secs;
 
 #[ComplexObject]
 impl Assignment {
@@ -50,6 +52,20 @@ impl Assignment {
         }
     }
 
+    async fn accommodations(&self, ctx: &Context<'_>) -> Result<Vec<AssignmentAccommodation>> {
+        document_quick_authorize(
+            ctx,
+            self.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let accommodations =
+            AssignmentAccommodation::find_all_by_assignment(&mut conn, self.id).format_err()?;
+        Ok(accommodations)
+    }
+
     async fn document(&self, ctx: &Context<'_>) -> Result<Document> {
         get_document(ctx, self.document_id).await
     }
@@ -98,6 +114,11 @@ impl Assignment {
 
         Ok(total_quiz)
     }
+
+    async fn max_score(&self, ctx: &Context<'_>) -> Result<f64> {
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        get_assignment_max_score(&mut conn, self).format_err()
+    }
 }
 
 #[ComplexObject]
@@ -145,6 +166,33 @@ impl Submission {
         }
     }
 
+    async fn max_score(&self, ctx: &Context<'_>) -> Result<f64> {
+        let assignment = self.assignment(ctx).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        get_submission_max_score(&mut conn, &assignment, self).format_err()
+    }
+
+    // Remaining time of a running test, computed by server to avoid clock skew of client
+    async fn seconds_remaining(&self) -> Option<i64> {
+        if self.submit_at.is_some() {
+            return None;
+        }
+
+        let deadline_at = self.deadline_at?;
+        let now = self.paused_at.unwrap_or_else(get_now_as_secs);
+        Some((deadline_at - now).max(0))
+    }
+
+    async fn percentage(&self, ctx: &Context<'_>) -> Option<f64> {
+        let grade = self.grade(ctx).await.ok()??;
+        let max_score = self.max_score(ctx).await.ok()?;
+        if max_score <= 0.0 {
+            return None;
+        }
+
+        Some(grade / max_score * 100.0)
+    }
+
     async fn final_grade(&self, ctx: &Context<'_>) -> Option<f64> {
         let user_auth = get_user_auth_from_ctx(ctx).await.ok()?;
         if user_auth.role != Role::Student {
@@ -171,6 +219,10 @@ impl RubricTableData {
     async fn total_user_score(&self) -> f64 {
         self.total_rubric_score()
     }
+
+    async fn max_score(&self) -> f64 {
+        self.max_rubric_score()
+    }
 }
 
 async fn get_document(ctx: &Context<'_>, document_id: Uuid) -> Result<Document> {
@@ -0,0 +1,796 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use actix::fut::wrap_future;
+use actix::*;
+use base64::prelude::*;
+use serde_json::Value;
+use tokio::sync::mpsc::Sender;
+use uuid::Uuid;
+use yrs::Doc;
+
+use crate::connection_pool::get_conn_from_actor;
+use crate::db::{Page, PageContent};
+use crate::error::IkigaiError;
+use crate::helper::{auto_create_page_content_version, refresh_assignment_max_score};
+use crate::util::get_now_as_secs;
+use crate::util::yjs_util::{apply_yjs_update, encode_yjs_state, new_yjs_doc, render_yjs_body};
+
+// Merged state of a page content is saved to its body in this period
+const PERSIST_INTERVAL_SECONDS: u64 = 10;
+
+#[derive(Enum, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CollaborationEventType {
+    // Full state of the room sent to a joined session, the session starts its document
+    // from this state instead of the body of the page content
+    Sync,
+    // The document of the room is replaced, e.g. the body is saved outside of the room
+    // or the room is started again. The session drops its document, including changes
+    // which are not sent yet, and starts again from the state in this event.
+    Reset,
+    Update,
+    Presence,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(complex)]
+pub struct CollaborationPresence {
+    pub session_id: Uuid,
+    pub user_id: i32,
+    pub is_editing: bool,
+    // Cursor or selection of the session in the editor
+    pub selection: Option<Value>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct CollaborationEvent {
+    pub page_content_id: Uuid,
+    pub event_type: CollaborationEventType,
+    pub origin_session_id: Option<Uuid>,
+    // Document of the room the updates belong to, it's changed when the room is reset
+    pub doc_id: Uuid,
+    // Base64 Yjs updates, they can be applied in any order and more than once
+    pub updates: Vec<String>,
+    // Clock of the latest update included in this event
+    pub clock: i64,
+    pub presences: Vec<CollaborationPresence>,
+}
+
+pub struct CollaborationSubscriber {
+    pub user_id: i32,
+    pub can_edit: bool,
+    pub sender: Sender<CollaborationEvent>,
+    pub presence: CollaborationPresence,
+}
+
+// Yjs document of a page content. It is started from the body of the page content,
+// updates of editors are merged into it and relayed to other sessions.
+pub struct CollaborationRoom {
+    pub doc_id: Uuid,
+    pub doc: Doc,
+    // Body of the page content the document is started from or saved as. The room only
+    // saves over this body, a body saved outside of the room resets the document.
+    pub saved_body: Value,
+    pub clock: i64,
+    // Merged state until this clock is saved to the page content
+    pub persisted_clock: i64,
+    pub is_persisting: bool,
+    // Editor of the latest update, the automatic version is created on behalf of them
+    pub last_editor_id: Option<i32>,
+    pub subscribers: HashMap<Uuid, CollaborationSubscriber>,
+}
+
+impl CollaborationRoom {
+    fn new(body: Value) -> Self {
+        Self {
+            doc_id: Uuid::new_v4(),
+            doc: new_yjs_doc(&body),
+            saved_body: body,
+            clock: 0,
+            persisted_clock: 0,
+            is_persisting: false,
+            last_editor_id: None,
+            subscribers: HashMap::new(),
+        }
+    }
+
+    // Start a new document from the saved body, changes which are not saved are dropped
+    fn reset(&mut self, body: Value) {
+        self.doc_id = Uuid::new_v4();
+        self.doc = new_yjs_doc(&body);
+        self.saved_body = body;
+        self.persisted_clock = self.clock;
+        self.last_editor_id = None;
+    }
+
+    fn get_presences(&self) -> Vec<CollaborationPresence> {
+        self.subscribers
+            .values()
+            .map(|subscriber| subscriber.presence.clone())
+            .collect()
+    }
+
+    fn new_event(
+        &self,
+        page_content_id: Uuid,
+        event_type: CollaborationEventType,
+        origin_session_id: Option<Uuid>,
+    ) -> CollaborationEvent {
+        CollaborationEvent {
+            page_content_id,
+            event_type,
+            origin_session_id,
+            doc_id: self.doc_id,
+            updates: vec![],
+            clock: self.clock,
+            presences: vec![],
+        }
+    }
+
+    fn new_state_event(
+        &self,
+        page_content_id: Uuid,
+        event_type: CollaborationEventType,
+    ) -> CollaborationEvent {
+        let mut event = self.new_event(page_content_id, event_type, None);
+        event.updates = vec![BASE64_STANDARD.encode(encode_yjs_state(&self.doc))];
+        event.presences = self.get_presences();
+        event
+    }
+}
+
+#[derive(Default)]
+pub struct CollaborationCenter {
+    // Page Content Id - Room
+    pub rooms: HashMap<Uuid, CollaborationRoom>,
+    pub ticks: u64,
+}
+
+impl Actor for CollaborationCenter {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(Duration::from_secs(1), |center, ctx| {
+            center.remove_closed_subscribers(ctx);
+
+            center.ticks += 1;
+            // Rooms without subscribers are saved right away, so they can be removed
+            let is_persisting_all = center.ticks % PERSIST_INTERVAL_SECONDS == 0;
+            center.persist_rooms(is_persisting_all, ctx);
+            center.rooms.retain(|_, room| {
+                !room.subscribers.is_empty()
+                    || room.is_persisting
+                    || room.clock > room.persisted_clock
+            });
+        });
+    }
+}
+
+impl Supervised for CollaborationCenter {}
+impl SystemService for CollaborationCenter {}
+
+#[derive(Message)]
+#[rtype(result = "Result<(), IkigaiError>")]
+pub struct CollaborationJoin {
+    pub page_content_id: Uuid,
+    pub session_id: Uuid,
+    pub user_id: i32,
+    pub can_edit: bool,
+    // Saved body of the page content, the room is started from it if it is not opened yet
+    pub body: Value,
+    // Document the session has from a previous join
+    pub doc_id: Option<Uuid>,
+    pub sender: Sender<CollaborationEvent>,
+}
+
+impl Handler<CollaborationJoin> for CollaborationCenter {
+    type Result = Result<(), IkigaiError>;
+
+    fn handle(&mut self, msg: CollaborationJoin, ctx: &mut Self::Context) -> Self::Result {
+        let CollaborationJoin {
+            page_content_id,
+            session_id,
+            user_id,
+            can_edit,
+            body,
+            doc_id,
+            sender,
+        } = msg;
+        let room = self
+            .rooms
+            .entry(page_content_id)
+            .or_insert_with(|| CollaborationRoom::new(body));
+        let is_other_user_session = room
+            .subscribers
+            .get(&session_id)
+            .map_or(false, |subscriber| subscriber.user_id != user_id);
+        if is_other_user_session {
+            return Err(IkigaiError::new_bad_request(
+                "Session is used by another user",
+            ));
+        }
+
+        let presence = CollaborationPresence {
+            session_id,
+            user_id,
+            is_editing: false,
+            selection: None,
+            updated_at: get_now_as_secs(),
+        };
+        room.subscribers.insert(
+            session_id,
+            CollaborationSubscriber {
+                user_id,
+                can_edit,
+                sender: sender.clone(),
+                presence,
+            },
+        );
+
+        // The room is started again since the session left, its document can't be merged
+        let event_type = match doc_id {
+            Some(doc_id) if doc_id != room.doc_id => CollaborationEventType::Reset,
+            _ => CollaborationEventType::Sync,
+        };
+        let event = room.new_state_event(page_content_id, event_type);
+        send_events(vec![(sender, event)], ctx);
+        self.broadcast_presences(page_content_id, ctx);
+        Ok(())
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<i64, IkigaiError>")]
+pub struct CollaborationUpdate {
+    pub page_content_id: Uuid,
+    pub session_id: Uuid,
+    pub user_id: i32,
+    pub doc_id: Uuid,
+    pub update: Vec<u8>,
+}
+
+impl Handler<CollaborationUpdate> for CollaborationCenter {
+    type Result = Result<i64, IkigaiError>;
+
+    fn handle(&mut self, msg: CollaborationUpdate, ctx: &mut Self::Context) -> Self::Result {
+        let room = self.find_editing_room(msg.page_content_id, msg.session_id, msg.user_id)?;
+        if msg.doc_id != room.doc_id {
+            return Err(IkigaiError::new_bad_request(
+                "Collaboration document is reset, the update is dropped",
+            ));
+        }
+        apply_yjs_update(&room.doc, &msg.update)?;
+        room.clock += 1;
+        room.last_editor_id = Some(msg.user_id);
+
+        let mut event = room.new_event(
+            msg.page_content_id,
+            CollaborationEventType::Update,
+            Some(msg.session_id),
+        );
+        event.updates = vec![BASE64_STANDARD.encode(&msg.update)];
+        let events: Vec<(Sender<CollaborationEvent>, CollaborationEvent)> = room
+            .subscribers
+            .iter()
+            .filter(|(session_id, _)| **session_id != msg.session_id)
+            .map(|(_, subscriber)| (subscriber.sender.clone(), event.clone()))
+            .collect();
+
+        let clock = room.clock;
+        send_events(events, ctx);
+        Ok(clock)
+    }
+}
+
+// The body of the page content is saved outside of the room
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct CollaborationReset {
+    pub page_content_id: Uuid,
+    pub body: Value,
+}
+
+impl Handler<CollaborationReset> for CollaborationCenter {
+    type Result = ();
+
+    fn handle(&mut self, msg: CollaborationReset, ctx: &mut Self::Context) -> Self::Result {
+        self.reset_room(msg.page_content_id, msg.body, ctx);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<(), IkigaiError>")]
+pub struct CollaborationPresenceChange {
+    pub page_content_id: Uuid,
+    pub session_id: Uuid,
+    pub user_id: i32,
+    pub is_editing: bool,
+    pub selection: Option<Value>,
+}
+
+impl Handler<CollaborationPresenceChange> for CollaborationCenter {
+    type Result = Result<(), IkigaiError>;
+
+    fn handle(
+        &mut self,
+        msg: CollaborationPresenceChange,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let subscriber = self
+            .rooms
+            .get_mut(&msg.page_content_id)
+            .and_then(|room| room.subscribers.get_mut(&msg.session_id))
+            .filter(|subscriber| subscriber.user_id == msg.user_id)
+            .ok_or_else(|| {
+                IkigaiError::new_bad_request("Session doesn't join the collaboration")
+            })?;
+        subscriber.presence.is_editing = msg.is_editing && subscriber.can_edit;
+        subscriber.presence.selection = msg.selection;
+        subscriber.presence.updated_at = get_now_as_secs();
+
+        self.broadcast_presences(msg.page_content_id, ctx);
+        Ok(())
+    }
+}
+
+impl CollaborationCenter {
+    fn find_editing_room(
+        &mut self,
+        page_content_id: Uuid,
+        session_id: Uuid,
+        user_id: i32,
+    ) -> Result<&mut CollaborationRoom, IkigaiError> {
+        let room = self.rooms.get_mut(&page_content_id).ok_or_else(|| {
+            IkigaiError::new_bad_request("Session doesn't join the collaboration")
+        })?;
+        let is_editor = room
+            .subscribers
+            .get(&session_id)
+            .map_or(false, |subscriber| {
+                subscriber.user_id == user_id && subscriber.can_edit
+            });
+        if !is_editor {
+            return Err(IkigaiError::new_bad_request(
+                "Session doesn't join the collaboration as an editor",
+            ));
+        }
+
+        Ok(room)
+    }
+
+    fn reset_room(
+        &mut self,
+        page_content_id: Uuid,
+        body: Value,
+        ctx: &mut <Self as Actor>::Context,
+    ) {
+        let Some(room) = self.rooms.get_mut(&page_content_id) else {
+            return;
+        };
+        if room.saved_body == body {
+            return;
+        }
+
+        room.reset(body);
+        let event = room.new_state_event(page_content_id, CollaborationEventType::Reset);
+        let events = room
+            .subscribers
+            .values()
+            .map(|subscriber| (subscriber.sender.clone(), event.clone()))
+            .collect();
+        send_events(events, ctx);
+    }
+
+    fn broadcast_presences(&self, page_content_id: Uuid, ctx: &mut <Self as Actor>::Context) {
+        if let Some(room) = self.rooms.get(&page_content_id) {
+            let mut event = room.new_event(page_content_id, CollaborationEventType::Presence, None);
+            event.presences = room.get_presences();
+            let events = room
+                .subscribers
+                .values()
+                .map(|subscriber| (subscriber.sender.clone(), event.clone()))
+                .collect();
+            send_events(events, ctx);
+        }
+    }
+
+    fn remove_closed_subscribers(&mut self, ctx: &mut <Self as Actor>::Context) {
+        let mut changed_rooms = vec![];
+        for (page_content_id, room) in self.rooms.iter_mut() {
+            let total_subscribers = room.subscribers.len();
+            room.subscribers
+                .retain(|_, subscriber| !subscriber.sender.is_closed());
+            if room.subscribers.len() != total_subscribers {
+                changed_rooms.push(*page_content_id);
+            }
+        }
+
+        for page_content_id in changed_rooms {
+            self.broadcast_presences(page_content_id, ctx);
+        }
+    }
+
+    // Save the body rendered from the merged state of changed rooms
+    fn persist_rooms(&mut self, is_persisting_all: bool, ctx: &mut <Self as Actor>::Context) {
+        let mut items = vec![];
+        for (page_content_id, room) in self.rooms.iter_mut() {
+            if room.is_persisting
+                || room.clock <= room.persisted_clock
+                || (!is_persisting_all && !room.subscribers.is_empty())
+            {
+                continue;
+            }
+
+            if let Some(user_id) = room.last_editor_id {
+                room.is_persisting = true;
+                items.push(PersistItem {
+                    page_content_id: *page_content_id,
+                    doc_id: room.doc_id,
+                    clock: room.clock,
+                    saved_body: room.saved_body.clone(),
+                    body: render_yjs_body(&room.doc),
+                    user_id,
+                });
+            }
+        }
+        if items.is_empty() {
+            return;
+        }
+
+        let task = async move {
+            let mut results = vec![];
+            match get_conn_from_actor().await {
+                Ok(mut conn) => {
+                    for item in items {
+                        let result = match persist_page_content_body(&mut conn, &item) {
+                            Ok(result) => result,
+                            Err(e) => {
+                                error!(
+                                    "Cannot save collaboration of {}: {:?}",
+                                    item.page_content_id, e
+                                );
+                                PersistResult::Failed
+                            }
+                        };
+                        results.push((item, result));
+                    }
+                }
+                Err(e) => {
+                    error!("Cannot save collaborations: {:?}", e);
+                    results = items
+                        .into_iter()
+                        .map(|item| (item, PersistResult::Failed))
+                        .collect();
+                }
+            }
+            results
+        };
+        wrap_future::<_, Self>(task)
+            .map(|results, center, ctx| {
+                for (item, result) in results {
+                    let page_content_id = item.page_content_id;
+                    let Some(room) = center.rooms.get_mut(&page_content_id) else {
+                        continue;
+                    };
+                    room.is_persisting = false;
+                    // The room is reset while saving
+                    if room.doc_id != item.doc_id {
+                        continue;
+                    }
+
+                    match result {
+                        PersistResult::Saved => {
+                            room.persisted_clock = room.persisted_clock.max(item.clock);
+                            room.saved_body = item.body;
+                        }
+                        PersistResult::Conflicted(body) => {
+                            center.reset_room(page_content_id, body, ctx);
+                        }
+                        PersistResult::Failed if room.subscribers.is_empty() => {
+                            // Nobody can send the changes again, the room is not kept forever
+                            error!("Collaboration of {page_content_id} is dropped");
+                            center.rooms.remove(&page_content_id);
+                        }
+                        PersistResult::Failed => {}
+                    }
+                }
+            })
+            .spawn(ctx);
+    }
+}
+
+struct PersistItem {
+    page_content_id: Uuid,
+    doc_id: Uuid,
+    clock: i64,
+    saved_body: Value,
+    body: Value,
+    user_id: i32,
+}
+
+enum PersistResult {
+    Saved,
+    // The body is saved outside of the room, along with the current body
+    Conflicted(Value),
+    Failed,
+}
+
+fn persist_page_content_body(
+    conn: &mut diesel::PgConnection,
+    item: &PersistItem,
+) -> Result<PersistResult, IkigaiError> {
+    let page_content = PageContent::find(conn, item.page_content_id)?;
+    if page_content.body != item.saved_body {
+        return Ok(PersistResult::Conflicted(page_content.body));
+    }
+    if page_content.body == item.body {
+        return Ok(PersistResult::Saved);
+    }
+
+    auto_create_page_content_version(conn, &page_content, item.user_id)?;
+    let updated = PageContent::update_body_if_unchanged(
+        conn,
+        item.page_content_id,
+        &item.saved_body,
+        &item.body,
+    )?;
+    let Some(page_content) = updated else {
+        let page_content = PageContent::find(conn, item.page_content_id)?;
+        return Ok(PersistResult::Conflicted(page_content.body));
+    };
+
+    // Quiz blocks can be added or removed by editing the content
+    let page = Page::find(conn, page_content.page_id)?;
+    refresh_assignment_max_score(conn, page.document_id)?;
+    Ok(PersistResult::Saved)
+}
+
+fn send_events(
+    events: Vec<(Sender<CollaborationEvent>, CollaborationEvent)>,
+    ctx: &mut <CollaborationCenter as Actor>::Context,
+) {
+    if events.is_empty() {
+        return;
+    }
+
+    let task = async move {
+        for (sender, event) in events {
+            let _ = sender.send(event).await;
+        }
+    };
+    wrap_future::<_, CollaborationCenter>(task).spawn(ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+    use serde_json::json;
+    use tokio::sync::mpsc::{channel, Receiver};
+    use yrs::{Text, Transact, XmlFragment, XmlOut};
+
+    use super::*;
+    use crate::util::yjs_util::YJS_FRAGMENT_NAME;
+
+    fn paragraph_body(text: &str) -> Value {
+        json!({
+            "type": "doc",
+            "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": text }] }]
+        })
+    }
+
+    async fn join(
+        center: &Addr<CollaborationCenter>,
+        page_content_id: Uuid,
+        body: Value,
+        doc_id: Option<Uuid>,
+    ) -> (Uuid, Receiver<CollaborationEvent>) {
+        let session_id = Uuid::new_v4();
+        let (sender, receiver) = channel(10);
+        center
+            .send(CollaborationJoin {
+                page_content_id,
+                session_id,
+                user_id: 1,
+                can_edit: true,
+                body,
+                doc_id,
+                sender,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        (session_id, receiver)
+    }
+
+    async fn next_event(
+        receiver: &mut Receiver<CollaborationEvent>,
+        event_type: CollaborationEventType,
+    ) -> CollaborationEvent {
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(1), receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if event.event_type == event_type {
+                return event;
+            }
+        }
+    }
+
+    // Body of a client document started from the state of the event
+    fn render_event_state(event: &CollaborationEvent) -> Value {
+        let doc = Doc::new();
+        for update in event.updates.iter() {
+            apply_yjs_update(&doc, &BASE64_STANDARD.decode(update).unwrap()).unwrap();
+        }
+        render_yjs_body(&doc)
+    }
+
+    // Update of a client which appends a chunk to the first paragraph
+    fn append_update(event: &CollaborationEvent, chunk: &str) -> Vec<u8> {
+        let doc = Doc::new();
+        for update in event.updates.iter() {
+            apply_yjs_update(&doc, &BASE64_STANDARD.decode(update).unwrap()).unwrap();
+        }
+        let fragment = doc.get_or_insert_xml_fragment(YJS_FRAGMENT_NAME);
+        let mut txn = doc.transact_mut();
+        let Some(XmlOut::Element(paragraph)) = fragment.get(&txn, 0) else {
+            panic!("Paragraph is missing");
+        };
+        let Some(XmlOut::Text(text)) = paragraph.get(&txn, 0) else {
+            panic!("Text is missing");
+        };
+        let position = text.len(&txn);
+        text.insert(&mut txn, position, chunk);
+        txn.encode_update_v1()
+    }
+
+    #[test]
+    fn join_with_document_of_previous_room_is_reset() {
+        System::new().block_on(async {
+            let center = CollaborationCenter::default().start();
+            let page_content_id = Uuid::new_v4();
+
+            let (_, mut receiver) =
+                join(&center, page_content_id, paragraph_body("Hello"), None).await;
+            let sync = next_event(&mut receiver, CollaborationEventType::Sync).await;
+            assert_eq!(render_event_state(&sync), paragraph_body("Hello"));
+
+            // Body of a room which is open is not used
+            let (_, mut receiver) = join(
+                &center,
+                page_content_id,
+                paragraph_body("Stale"),
+                Some(sync.doc_id),
+            )
+            .await;
+            let event = next_event(&mut receiver, CollaborationEventType::Sync).await;
+            assert_eq!(event.doc_id, sync.doc_id);
+            assert_eq!(render_event_state(&event), paragraph_body("Hello"));
+
+            let (_, mut receiver) = join(
+                &center,
+                page_content_id,
+                paragraph_body("Stale"),
+                Some(Uuid::new_v4()),
+            )
+            .await;
+            let event = next_event(&mut receiver, CollaborationEventType::Reset).await;
+            assert_eq!(event.doc_id, sync.doc_id);
+            assert_eq!(render_event_state(&event), paragraph_body("Hello"));
+        });
+    }
+
+    #[test]
+    fn body_saved_outside_resets_room() {
+        System::new().block_on(async {
+            let center = CollaborationCenter::default().start();
+            let page_content_id = Uuid::new_v4();
+            let (session_id, mut receiver) =
+                join(&center, page_content_id, paragraph_body("Hello"), None).await;
+            let sync = next_event(&mut receiver, CollaborationEventType::Sync).await;
+
+            let update = |doc_id: Uuid, update: Vec<u8>| CollaborationUpdate {
+                page_content_id,
+                session_id,
+                user_id: 1,
+                doc_id,
+                update,
+            };
+            let clock = center
+                .send(update(sync.doc_id, append_update(&sync, " world")))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(clock, 1);
+
+            center
+                .send(CollaborationReset {
+                    page_content_id,
+                    body: paragraph_body("Saved elsewhere"),
+                })
+                .await
+                .unwrap();
+            let reset = next_event(&mut receiver, CollaborationEventType::Reset).await;
+            assert_ne!(reset.doc_id, sync.doc_id);
+            assert_eq!(
+                render_event_state(&reset),
+                paragraph_body("Saved elsewhere")
+            );
+
+            // Updates of the previous document are not merged into the new one
+            let result = center
+                .send(update(sync.doc_id, append_update(&sync, "!")))
+                .await
+                .unwrap();
+            assert!(result.is_err());
+
+            center
+                .send(update(reset.doc_id, append_update(&reset, "!")))
+                .await
+                .unwrap()
+                .unwrap();
+            let (_, mut receiver) =
+                join(&center, page_content_id, paragraph_body("Stale"), None).await;
+            let event = next_event(&mut receiver, CollaborationEventType::Sync).await;
+            assert_eq!(
+                render_event_state(&event),
+                paragraph_body("Saved elsewhere!")
+            );
+        });
+    }
+
+    #[test]
+    fn restored_body_is_not_saved_over() {
+        let mut room = CollaborationRoom::new(paragraph_body("Draft"));
+        let sync = room.new_state_event(Uuid::new_v4(), CollaborationEventType::Sync);
+        apply_yjs_update(&room.doc, &append_update(&sync, " edited")).unwrap();
+        room.clock += 1;
+        room.last_editor_id = Some(1);
+
+        // Quiz block ids can be replaced by restoring
+        let restored = json!({
+            "type": "doc",
+            "content": [
+                { "type": "paragraph", "content": [{ "type": "text", "text": "Version 1" }] },
+                { "type": "quizBlock", "attrs": { "quizId": Uuid::new_v4().to_string() } }
+            ]
+        });
+        let doc_id = room.doc_id;
+        room.reset(restored.clone());
+
+        assert_ne!(room.doc_id, doc_id);
+        assert_eq!(render_yjs_body(&room.doc), restored);
+        assert_eq!(room.saved_body, restored);
+        // Edits before restoring are not saved over the restored body
+        assert_eq!(room.persisted_clock, room.clock);
+        assert_eq!(room.last_editor_id, None);
+    }
+
+    #[test]
+    fn session_of_other_user_cannot_join() {
+        System::new().block_on(async {
+            let center = CollaborationCenter::default().start();
+            let page_content_id = Uuid::new_v4();
+            let (session_id, _receiver) =
+                join(&center, page_content_id, paragraph_body("Hello"), None).await;
+
+            let (sender, _) = channel(10);
+            let result = center
+                .send(CollaborationJoin {
+                    page_content_id,
+                    session_id,
+                    user_id: 2,
+                    can_edit: true,
+                    body: paragraph_body("Hello"),
+                    doc_id: None,
+                    sender,
+                })
+                .await
+                .unwrap();
+            assert!(result.is_err());
+        });
+    }
+}
@@ -537,6 +537,43 @@ impl Loader<FindDocumentTag> for IkigaiDataLoader {
     }
 }
 
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct FindBankQuizTag {
+    pub bank_quiz_id: Uuid,
+}
+
+impl Loader<FindBankQuizTag> for IkigaiDataLoader {
+    type Value = Vec<BankQuizTag>;
+    type Error = IkigaiError;
+
+    async fn load(
+        &self,
+        keys: &[FindBankQuizTag],
+    ) -> std::result::Result<HashMap<FindBankQuizTag, Self::Value>, Self::Error> {
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let bank_quiz_ids = keys.iter().map(|key| key.bank_quiz_id).unique().collect();
+
+        let mut conn = get_conn_from_actor().await?;
+        let tags = BankQuizTag::find_by_bank_quiz_ids(&mut conn, &bank_quiz_ids)?;
+
+        let mut res: HashMap<FindBankQuizTag, Self::Value> = HashMap::new();
+        for tag in tags {
+            let key = FindBankQuizTag {
+                bank_quiz_id: tag.bank_quiz_id,
+            };
+            if let Some(inner_tags) = res.get_mut(&key) {
+                inner_tags.push(tag);
+            } else {
+                res.insert(key, vec![tag]);
+            }
+        }
+
+        Ok(res)
+    }
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct FindEmbeddedSessionResponses {
     pub embedded_session_id: Uuid,
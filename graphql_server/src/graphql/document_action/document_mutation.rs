@@ -1,5 +1,7 @@
 use crate::authentication_token::Claims;
+use actix::SystemService;
 use async_graphql::*;
+use base64::prelude::*;
 use diesel::Connection;
 use itertools::Itertools;
 use uuid::Uuid;
@@ -7,10 +9,14 @@ use uuid::Uuid;
 use crate::authorization::{DocumentActionPermission, SpaceActionPermission};
 use crate::db::*;
 use crate::error::{IkigaiError, IkigaiErrorExt};
+use crate::graphql::collaboration_center::*;
 use crate::helper::*;
 use crate::notification_center::send_notification;
 use crate::util::get_now_as_secs;
 
+// Base64 length of a collaboration update, around 1MB of binary data
+const MAX_COLLABORATION_UPDATE_LENGTH: usize = 1_400_000;
+
 #[derive(SimpleObject)]
 pub struct AccessTokenWithSubmission {
     pub access_token: Option<String>,
@@ -200,6 +206,7 @@ impl DocumentMutation {
 
         let mut conn = get_conn_from_ctx(ctx).await?;
         Page::soft_delete(&mut conn, page_id).format_err()?;
+        refresh_assignment_max_score(&mut conn, page.document_id).format_err()?;
 
         Ok(true)
     }
@@ -218,6 +225,7 @@ impl DocumentMutation {
 
         let mut conn = get_conn_from_ctx(ctx).await?;
         let page = Page::restore(&mut conn, page_id).format_err()?;
+        refresh_assignment_max_score(&mut conn, page.document_id).format_err()?;
 
         Ok(page)
     }
@@ -238,19 +246,133 @@ impl DocumentMutation {
         )
         .await?;
 
+        let user_id = get_user_id_from_ctx(ctx).await?;
         let mut conn = get_conn_from_ctx(ctx).await?;
         let existing_page_content = PageContent::find(&mut conn, page_content.id);
-        if existing_page_content.map(|content| content.page_id) != Ok(page.id) {
+        if existing_page_content
+            .as_ref()
+            .map(|content| content.page_id)
+            != Ok(page.id)
+        {
             return Err(IkigaiError::new_bad_request(
                 "Cannot update content of other page",
             ))
             .format_err();
         }
+        if let Ok(existing_page_content) = existing_page_content {
+            auto_create_page_content_version(&mut conn, &existing_page_content, user_id)
+                .format_err()?;
+        }
 
         let content = PageContent::upsert(&mut conn, page_content).format_err()?;
+        // Quiz blocks can be added or removed by editing the content
+        refresh_assignment_max_score(&mut conn, page.document_id).format_err()?;
+        reset_collaboration(&content).await?;
         Ok(content)
     }
 
+    async fn document_create_page_content_version(
+        &self,
+        ctx: &Context<'_>,
+        page_content_id: Uuid,
+        name: Option<String>,
+    ) -> Result<PageContentVersion> {
+        document_quick_allowed_by_page_content(
+            ctx,
+            page_content_id,
+            DocumentActionPermission::EditDocument,
+        )
+        .await?;
+
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let page_content = PageContent::find(&mut conn, page_content_id).format_err()?;
+        create_page_content_version(&mut conn, &page_content, name, false, user_id).format_err()
+    }
+
+    async fn document_restore_page_content_version(
+        &self,
+        ctx: &Context<'_>,
+        version_id: Uuid,
+    ) -> Result<PageContent> {
+        let version = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            PageContentVersion::find(&mut conn, version_id).format_err()?
+        };
+        document_quick_allowed_by_page_content(
+            ctx,
+            version.page_content_id,
+            DocumentActionPermission::EditDocument,
+        )
+        .await?;
+
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let content = restore_page_content_version(&mut conn, &version, user_id).format_err()?;
+        // Otherwise the next save of the collaboration undoes the restore
+        reset_collaboration(&content).await?;
+        Ok(content)
+    }
+
+    async fn document_collaboration_update(
+        &self,
+        ctx: &Context<'_>,
+        page_content_id: Uuid,
+        session_id: Uuid,
+        doc_id: Uuid,
+        update: String,
+    ) -> Result<i64> {
+        document_quick_allowed_by_page_content(
+            ctx,
+            page_content_id,
+            DocumentActionPermission::EditDocument,
+        )
+        .await?;
+        let update = decode_collaboration_update(&update)?;
+
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        let clock = CollaborationCenter::from_registry()
+            .send(CollaborationUpdate {
+                page_content_id,
+                session_id,
+                user_id,
+                doc_id,
+                update,
+            })
+            .await?
+            .format_err()?;
+        Ok(clock)
+    }
+
+    async fn document_collaboration_presence(
+        &self,
+        ctx: &Context<'_>,
+        page_content_id: Uuid,
+        session_id: Uuid,
+        is_editing: bool,
+        selection: Option<serde_json::Value>,
+    ) -> Result<bool> {
+        document_quick_allowed_by_page_content(
+            ctx,
+            page_content_id,
+            DocumentActionPermission::ViewDocument,
+        )
+        .await?;
+
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        CollaborationCenter::from_registry()
+            .send(CollaborationPresenceChange {
+                page_content_id,
+                session_id,
+                user_id,
+                is_editing,
+                selection,
+            })
+            .await?
+            .format_err()?;
+        Ok(true)
+    }
+
     async fn document_assign(
         &self,
         ctx: &Context<'_>,
@@ -468,3 +590,28 @@ impl DocumentMutation {
         }
     }
 }
+
+// Collaborating sessions start again from the body saved outside of the collaboration
+async fn reset_collaboration(page_content: &PageContent) -> Result<()> {
+    CollaborationCenter::from_registry()
+        .send(CollaborationReset {
+            page_content_id: page_content.id,
+            body: page_content.body.clone(),
+        })
+        .await?;
+    Ok(())
+}
+
+// Yjs update encoded in base64
+fn decode_collaboration_update(update: &str) -> Result<Vec<u8>> {
+    if update.len() > MAX_COLLABORATION_UPDATE_LENGTH {
+        return Err(IkigaiError::new_bad_request(
+            "Collaboration update is too large",
+        ))
+        .format_err();
+    }
+    BASE64_STANDARD
+        .decode(update)
+        .map_err(|_| IkigaiError::new_bad_request("Collaboration update must be encoded in base64"))
+        .format_err()
+}
@@ -52,6 +52,57 @@ impl DocumentQuery {
         Ok(assignees)
     }
 
+    async fn document_page_content_versions(
+        &self,
+        ctx: &Context<'_>,
+        page_content_id: Uuid,
+    ) -> Result<Vec<PageContentVersion>> {
+        document_quick_allowed_by_page_content(
+            ctx,
+            page_content_id,
+            DocumentActionPermission::EditDocument,
+        )
+        .await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        PageContentVersion::find_all_by_page_content(&mut conn, page_content_id).format_err()
+    }
+
+    // Compare with the current content if the second version is not given
+    async fn document_diff_page_content_versions(
+        &self,
+        ctx: &Context<'_>,
+        from_version_id: Uuid,
+        to_version_id: Option<Uuid>,
+    ) -> Result<Vec<PageContentBlockDiff>> {
+        let (from_version, to_body) = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            let from_version = PageContentVersion::find(&mut conn, from_version_id).format_err()?;
+            let (to_page_content_id, to_body) = if let Some(to_version_id) = to_version_id {
+                let to_version = PageContentVersion::find(&mut conn, to_version_id).format_err()?;
+                (to_version.page_content_id, to_version.body)
+            } else {
+                let page_content =
+                    PageContent::find(&mut conn, from_version.page_content_id).format_err()?;
+                (page_content.id, page_content.body)
+            };
+            if to_page_content_id != from_version.page_content_id {
+                return Err(IkigaiError::new_bad_request(
+                    "Versions are not of the same page content",
+                ))
+                .format_err();
+            }
+            (from_version, to_body)
+        };
+        document_quick_allowed_by_page_content(
+            ctx,
+            from_version.page_content_id,
+            DocumentActionPermission::EditDocument,
+        )
+        .await?;
+
+        Ok(diff_page_content_body(&from_version.body, &to_body))
+    }
+
     async fn document_get_embedded_session(
         &self,
         ctx: &Context<'_>,
@@ -11,6 +11,7 @@ use uuid::Uuid;
 
 use crate::db::*;
 use crate::error::IkigaiErrorExt;
+use crate::graphql::collaboration_center::CollaborationPresence;
 use crate::graphql::data_loader::*;
 use crate::helper::{
     document_quick_authorize, generate_download_url, get_conn_from_ctx,
@@ -140,6 +141,20 @@ impl PageContent {
     }
 }
 
+#[ComplexObject]
+impl CollaborationPresence {
+    async fn user(&self, ctx: &Context<'_>) -> Result<PublicUser> {
+        get_public_user_from_loader(ctx, self.user_id).await
+    }
+}
+
+#[ComplexObject]
+impl PageContentVersion {
+    async fn creator(&self, ctx: &Context<'_>) -> Result<PublicUser> {
+        get_public_user_from_loader(ctx, self.creator_id).await
+    }
+}
+
 #[ComplexObject]
 impl DocumentAssignedUsers {
     async fn user(&self, ctx: &Context<'_>) -> Result<PublicUser> {
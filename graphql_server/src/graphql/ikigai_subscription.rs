@@ -1,14 +1,18 @@
 use actix::SystemService;
 
-use crate::db::Submission;
+use crate::db::{Page, PageContent, Submission};
 use crate::error::IkigaiErrorExt;
 use async_graphql::*;
 use futures_core::Stream;
 use tokio::sync::mpsc::channel;
+use uuid::Uuid;
 
 use crate::authorization::DocumentActionPermission;
+use crate::graphql::collaboration_center::*;
 use crate::graphql::notification_center::*;
-use crate::helper::{document_quick_authorize, get_conn_from_ctx, get_user_id_from_ctx};
+use crate::helper::{
+    document_is_allowed, document_quick_authorize, get_conn_from_ctx, get_user_id_from_ctx,
+};
 
 #[derive(MergedObject, Default)]
 pub struct Subscription;
@@ -21,7 +25,7 @@ impl Subscription {
         submission_id: i32,
     ) -> Result<impl Stream<Item = SubmissionEvent>> {
         let user_id = get_user_id_from_ctx(ctx).await?;
-        {
+        let deadline_at = {
             // I want release conn when this scope released.
             // Ensure that this document is existing
             let mut conn = get_conn_from_ctx(ctx).await?;
@@ -32,13 +36,22 @@ impl Subscription {
                 DocumentActionPermission::ViewDocument,
             )
             .await?;
-        }
+
+            // Countdown is only for the student doing the test
+            let is_running = submission.submit_at.is_none() && submission.paused_at.is_none();
+            if submission.user_id == user_id && is_running {
+                submission.deadline_at
+            } else {
+                None
+            }
+        };
         let (sender, mut receiver) = channel(100);
         NotificationCenter::from_registry()
             .send(SubmissionSubscribe {
                 user_id,
                 submission_id,
                 sender,
+                deadline_at,
             })
             .await?;
 
@@ -48,4 +61,51 @@ impl Subscription {
             }
         })
     }
+
+    // Collaborative editing of a page content, the session id is generated by the client
+    // and used to send updates and presence of the session. A session joining again sends
+    // the document id it has, so it's reset if the document of the room is changed.
+    async fn page_content_collaborate(
+        &self,
+        ctx: &Context<'_>,
+        page_content_id: Uuid,
+        session_id: Uuid,
+        doc_id: Option<Uuid>,
+    ) -> Result<impl Stream<Item = CollaborationEvent>> {
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        let (document_id, body) = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            let page_content = PageContent::find(&mut conn, page_content_id).format_err()?;
+            let page = Page::find(&mut conn, page_content.page_id).format_err()?;
+            (page.document_id, page_content.body)
+        };
+        document_quick_authorize(ctx, document_id, DocumentActionPermission::ViewDocument).await?;
+        let can_edit = document_is_allowed(
+            ctx,
+            Some(user_id),
+            document_id,
+            DocumentActionPermission::EditDocument,
+        )
+        .await?;
+
+        let (sender, mut receiver) = channel(100);
+        CollaborationCenter::from_registry()
+            .send(CollaborationJoin {
+                page_content_id,
+                session_id,
+                user_id,
+                can_edit,
+                body,
+                doc_id,
+                sender,
+            })
+            .await?
+            .format_err()?;
+
+        Ok(async_stream::stream! {
+            while let Some(item) = receiver.recv().await {
+                yield item;
+            }
+        })
+    }
 }
@@ -11,6 +11,7 @@ use crate::service::redis::Redis;
 use crate::util::log_util::Logger;
 
 pub mod assignment_action;
+pub mod collaboration_center;
 pub mod context_caching_data;
 pub mod data_loader;
 pub mod document_action;
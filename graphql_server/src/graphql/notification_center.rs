@@ -10,31 +10,51 @@ use crate::util::get_now_as_secs;
 #[derive(Enum, Debug, Copy, Clone, Eq, PartialEq)]
 pub enum SubmissionEventType {
     SubmitCompleted,
+    DeadlineChanged,
+    // Periodic countdown of a running test
+    Tick,
+    // Test is about to end, e.g. 5 minutes left
+    TimeWarning,
 }
 
 #[derive(Debug, Clone, Copy, SimpleObject)]
 pub struct SubmissionEvent {
     pub submission_id: i32,
     pub event_type: SubmissionEventType,
+    pub seconds_remaining: Option<i64>,
+}
+
+const COUNTDOWN_TICK_SECONDS: i64 = 30;
+const COUNTDOWN_WARNING_SECONDS: [i64; 2] = [300, 60];
+
+#[derive(Debug, Clone, Copy)]
+pub struct SubmissionCountdown {
+    pub user_id: i32,
+    pub deadline_at: i64,
+    pub last_seconds_remaining: i64,
 }
 
 #[derive(Default)]
 pub struct NotificationCenter {
     // User Id - Document Id - (Time start listen, Document Event Listener)
     pub submission_subscribers: HashMap<i32, HashMap<i32, Vec<(i64, Sender<SubmissionEvent>)>>>,
+    // Submission Id - Countdown of running tests which are listened
+    pub submission_countdowns: HashMap<i32, SubmissionCountdown>,
 }
 
 impl Actor for NotificationCenter {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        ctx.run_interval(Duration::from_secs(1), |center, _| {
+        ctx.run_interval(Duration::from_secs(1), |center, ctx| {
             for (_, submissions) in center.submission_subscribers.iter_mut() {
                 for (_, senders) in submissions.iter_mut() {
                     // Only keep open sender
                     senders.retain(|(_, sender)| !sender.is_closed());
                 }
             }
+
+            center.send_countdown_events(ctx);
         });
     }
 }
@@ -48,6 +68,8 @@ pub struct SubmissionSubscribe {
     pub user_id: i32,
     pub submission_id: i32,
     pub sender: Sender<SubmissionEvent>,
+    // Deadline of the running test, none in case it's not timed, paused or submitted
+    pub deadline_at: Option<i64>,
 }
 
 impl Handler<SubmissionSubscribe> for NotificationCenter {
@@ -68,6 +90,8 @@ impl Handler<SubmissionSubscribe> for NotificationCenter {
             submissions.insert(msg.submission_id, vec![item]);
             self.submission_subscribers.insert(msg.user_id, submissions);
         }
+
+        self.set_countdown(msg.user_id, msg.submission_id, msg.deadline_at);
     }
 }
 
@@ -85,10 +109,106 @@ impl Handler<SubmitCompleted> for NotificationCenter {
         let event = SubmissionEvent {
             submission_id: msg.submission_id,
             event_type: SubmissionEventType::SubmitCompleted,
+            seconds_remaining: None,
         };
+        self.submission_countdowns.remove(&msg.submission_id);
+        self.send_submission_event(msg.user_id, event, ctx);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SubmissionDeadlineChanged {
+    pub user_id: i32,
+    pub submission_id: i32,
+    // None in case the test is paused
+    pub deadline_at: Option<i64>,
+}
+
+impl Handler<SubmissionDeadlineChanged> for NotificationCenter {
+    type Result = ();
+
+    fn handle(&mut self, msg: SubmissionDeadlineChanged, ctx: &mut Self::Context) -> Self::Result {
+        let event = SubmissionEvent {
+            submission_id: msg.submission_id,
+            event_type: SubmissionEventType::DeadlineChanged,
+            seconds_remaining: msg
+                .deadline_at
+                .map(|deadline_at| (deadline_at - get_now_as_secs()).max(0)),
+        };
+        self.set_countdown(msg.user_id, msg.submission_id, msg.deadline_at);
+        self.send_submission_event(msg.user_id, event, ctx);
+    }
+}
+
+impl NotificationCenter {
+    fn set_countdown(&mut self, user_id: i32, submission_id: i32, deadline_at: Option<i64>) {
+        if let Some(deadline_at) = deadline_at {
+            let countdown = SubmissionCountdown {
+                user_id,
+                deadline_at,
+                last_seconds_remaining: (deadline_at - get_now_as_secs()).max(0),
+            };
+            self.submission_countdowns.insert(submission_id, countdown);
+        } else {
+            self.submission_countdowns.remove(&submission_id);
+        }
+    }
+
+    fn send_countdown_events(&mut self, ctx: &mut <Self as Actor>::Context) {
+        let now = get_now_as_secs();
+        let mut events = vec![];
+        for (submission_id, countdown) in self.submission_countdowns.iter_mut() {
+            let previous_seconds_remaining = countdown.last_seconds_remaining;
+            let seconds_remaining = (countdown.deadline_at - now).max(0);
+            countdown.last_seconds_remaining = seconds_remaining;
+
+            let is_warning = COUNTDOWN_WARNING_SECONDS.iter().any(|warning_seconds| {
+                previous_seconds_remaining > *warning_seconds
+                    && seconds_remaining <= *warning_seconds
+            });
+            let is_tick = previous_seconds_remaining / COUNTDOWN_TICK_SECONDS
+                != seconds_remaining / COUNTDOWN_TICK_SECONDS;
+            let event_type = if is_warning {
+                SubmissionEventType::TimeWarning
+            } else if is_tick {
+                SubmissionEventType::Tick
+            } else {
+                continue;
+            };
+
+            let event = SubmissionEvent {
+                submission_id: *submission_id,
+                event_type,
+                seconds_remaining: Some(seconds_remaining),
+            };
+            events.push((countdown.user_id, event));
+        }
+
+        // Stop counting when the test is over or nobody listens to it
+        let subscribers = &self.submission_subscribers;
+        self.submission_countdowns
+            .retain(|submission_id, countdown| {
+                countdown.last_seconds_remaining > 0
+                    && subscribers
+                        .get(&countdown.user_id)
+                        .and_then(|submissions| submissions.get(submission_id))
+                        .map_or(false, |senders| !senders.is_empty())
+            });
+
+        for (user_id, event) in events {
+            self.send_submission_event(user_id, event, ctx);
+        }
+    }
 
-        if let Some(submissions) = self.submission_subscribers.get(&msg.user_id) {
-            if let Some(senders) = submissions.get(&msg.submission_id) {
+    fn send_submission_event(
+        &self,
+        user_id: i32,
+        event: SubmissionEvent,
+        ctx: &mut <Self as Actor>::Context,
+    ) {
+        if let Some(submissions) = self.submission_subscribers.get(&user_id) {
+            if let Some(senders) = submissions.get(&event.submission_id) {
                 let cloned_senders = senders.clone();
                 let task = async move {
                     for (_, sender) in cloned_senders {
@@ -11,13 +11,20 @@ use uuid::Uuid;
 
 use crate::authorization::DocumentActionPermission;
 use crate::db::{
-    ChoiceAnswerData, ChoiceOption, ChoiceQuestionData, ChoiceUserAnswerData,
-    FillInBlankAnswerData, FillInBlankQuestionData, FillInBlankUserAnswerData, Quiz,
+    BankQuiz, BankQuizTag, ChoiceAnswerData, ChoiceOption, ChoiceQuestionData,
+    ChoiceUserAnswerData, FillInBlankAnswerData, FillInBlankExpectedAnswer,
+    FillInBlankQuestionData, FillInBlankUserAnswerData, MatchingAnswerData, MatchingQuestionData,
+    MatchingUserAnswerData, NumericAnswerData, NumericQuestionData, NumericUserAnswerData,
+    OrderingAnswerData, OrderingQuestionData, OrderingUserAnswerData, PublicUser, Quiz,
     QuizUserAnswer, SelectAnswerData, SelectQuestionData, SelectUserAnswerData,
     WritingQuestionData,
 };
-use crate::graphql::data_loader::{FindQuiz, FindQuizUserAnswersByQuiz, IkigaiDataLoader};
-use crate::helper::{document_quick_allowed_by_page_content, get_user_id_from_ctx};
+use crate::graphql::data_loader::{
+    FindBankQuizTag, FindQuiz, FindQuizUserAnswersByQuiz, IkigaiDataLoader,
+};
+use crate::helper::{
+    document_quick_allowed_by_page_content, get_public_user_from_loader, get_user_id_from_ctx,
+};
 use crate::service::ikigai_ai::{
     AIFillInBlankQuiz, AIMultipleChoiceQuiz, AISelectOptionQuiz, AISingleChoiceQuiz,
 };
@@ -131,6 +138,30 @@ impl Quiz {
     ) -> Option<FillInBlankAnswerData> {
         serde_json::from_value(self.answer_data(ctx).await.ok()??).ok()
     }
+
+    async fn ordering_question(&self, ctx: &Context<'_>) -> Option<OrderingQuestionData> {
+        serde_json::from_value(self.question_data(ctx).await.ok()??).ok()
+    }
+
+    async fn ordering_expected_answer(&self, ctx: &Context<'_>) -> Option<OrderingAnswerData> {
+        serde_json::from_value(self.answer_data(ctx).await.ok()??).ok()
+    }
+
+    async fn matching_question(&self, ctx: &Context<'_>) -> Option<MatchingQuestionData> {
+        serde_json::from_value(self.question_data(ctx).await.ok()??).ok()
+    }
+
+    async fn matching_expected_answer(&self, ctx: &Context<'_>) -> Option<MatchingAnswerData> {
+        serde_json::from_value(self.answer_data(ctx).await.ok()??).ok()
+    }
+
+    async fn numeric_question(&self, ctx: &Context<'_>) -> Option<NumericQuestionData> {
+        serde_json::from_value(self.question_data(ctx).await.ok()??).ok()
+    }
+
+    async fn numeric_expected_answer(&self, ctx: &Context<'_>) -> Option<NumericAnswerData> {
+        serde_json::from_value(self.answer_data(ctx).await.ok()??).ok()
+    }
 }
 
 #[ComplexObject]
@@ -154,6 +185,21 @@ impl QuizUserAnswer {
         Some(self.score)
     }
 
+    async fn manual_score(&self, ctx: &Context<'_>) -> Option<f64> {
+        self.score(ctx).await.ok()??;
+        self.manual_score
+    }
+
+    async fn comment(&self, ctx: &Context<'_>) -> Option<String> {
+        self.score(ctx).await.ok()??;
+        self.comment.clone()
+    }
+
+    async fn graded_at(&self, ctx: &Context<'_>) -> Option<i64> {
+        self.score(ctx).await.ok()??;
+        self.graded_at
+    }
+
     async fn single_choice_answer(&self) -> Option<ChoiceUserAnswerData> {
         self.parse_answer_data()
     }
@@ -169,6 +215,36 @@ impl QuizUserAnswer {
     async fn fill_in_blank_answer(&self) -> Option<FillInBlankUserAnswerData> {
         self.parse_answer_data()
     }
+
+    async fn ordering_answer(&self) -> Option<OrderingUserAnswerData> {
+        self.parse_answer_data()
+    }
+
+    async fn matching_answer(&self) -> Option<MatchingUserAnswerData> {
+        self.parse_answer_data()
+    }
+
+    async fn numeric_answer(&self) -> Option<NumericUserAnswerData> {
+        self.parse_answer_data()
+    }
+}
+
+#[ComplexObject]
+impl BankQuiz {
+    async fn creator(&self, ctx: &Context<'_>) -> Result<PublicUser> {
+        get_public_user_from_loader(ctx, self.creator_id).await
+    }
+
+    async fn tags(&self, ctx: &Context<'_>) -> Result<Vec<BankQuizTag>> {
+        let loader = ctx.data_unchecked::<DataLoader<IkigaiDataLoader>>();
+        let tags = loader
+            .load_one(FindBankQuizTag {
+                bank_quiz_id: self.id,
+            })
+            .await?
+            .unwrap_or_default();
+        Ok(tags)
+    }
 }
 
 impl AISingleChoiceQuiz {
@@ -238,10 +314,7 @@ impl AIFillInBlankQuiz {
 
         let question = FillInBlankQuestionData { content: None };
 
-        let expected_answer = ChoiceOption {
-            id: Uuid::new_v4(),
-            content: correct_answer,
-        };
+        let expected_answer = FillInBlankExpectedAnswer::new(correct_answer);
         let expected_answer_data = FillInBlankAnswerData {
             expected_answers: vec![expected_answer],
         };
@@ -1,8 +1,9 @@
 use async_graphql::*;
+use diesel::Connection;
 use serde::Serialize;
 use uuid::Uuid;
 
-use crate::authorization::DocumentActionPermission;
+use crate::authorization::{DocumentActionPermission, SpaceActionPermission};
 use crate::db::*;
 use crate::error::{IkigaiError, IkigaiErrorExt};
 use crate::helper::*;
@@ -10,7 +11,6 @@ use crate::service::ikigai_ai::{
     AIFillInBlankQuiz, AIGenerateQuizResponse, AIMultipleChoiceQuiz, AISelectOptionQuiz,
     AISingleChoiceQuiz, GenerateQuizzesRequestData, IkigaiAI,
 };
-use crate::util::{end_of_today, start_of_today};
 
 #[derive(Default)]
 pub struct QuizMutation;
@@ -22,6 +22,7 @@ impl QuizMutation {
         ctx: &Context<'_>,
         page_content_id: Uuid,
         mut data: Quiz,
+        points: Option<f64>,
     ) -> Result<Quiz> {
         let user_id = get_user_id_from_ctx(ctx).await?;
         let page = {
@@ -36,9 +37,16 @@ impl QuizMutation {
         )
         .await?;
 
+        if points.map_or(false, |points| !points.is_finite() || points < 0.0) {
+            return Err(IkigaiError::new_bad_request(
+                "Points must be a non-negative number",
+            ))
+            .format_err();
+        }
+
         let mut conn = get_conn_from_ctx(ctx).await?;
-        let existing_quiz_block = Quiz::find(&mut conn, data.id).format_err();
-        if let Ok(quiz_block) = existing_quiz_block {
+        let existing_quiz_block = Quiz::find(&mut conn, data.id).ok();
+        if let Some(quiz_block) = &existing_quiz_block {
             if quiz_block.page_content_id != page_content_id {
                 return Err(IkigaiError::new_bad_request(
                     "Cannot update quiz block of other page",
@@ -47,10 +55,33 @@ impl QuizMutation {
             }
         }
 
+        let submission = Submission::find_by_document(&mut conn, page.document_id).format_err()?;
+        if submission.is_some() {
+            // Students edit their copy of the assignment quiz (e.g. writing into a writing block),
+            // it keeps its lineage and the points of the assignment quiz.
+            let original_quiz_id = existing_quiz_block
+                .as_ref()
+                .and_then(|quiz_block| quiz_block.original_quiz_id);
+            let original_points = original_quiz_id
+                .and_then(|original_quiz_id| Quiz::find(&mut conn, original_quiz_id).ok())
+                .map(|original_quiz| original_quiz.points);
+            data.original_quiz_id = original_quiz_id;
+            data.points = original_points
+                .or(existing_quiz_block.map(|quiz_block| quiz_block.points))
+                .unwrap_or_default();
+        } else {
+            data.original_quiz_id = None;
+            data.points = points
+                .or(existing_quiz_block.map(|quiz_block| quiz_block.points))
+                .unwrap_or(1.0);
+        }
+
         data.page_content_id = page_content_id;
         data.creator_id = user_id;
-        data.original_quiz_id = None;
+        // Quiz edited in place is no longer a copy of a bank quiz
+        data.bank_quiz_id = None;
         let quiz = Quiz::upsert(&mut conn, data).format_err()?;
+        refresh_assignment_max_score(&mut conn, page.document_id).format_err()?;
 
         Ok(quiz)
     }
@@ -102,6 +133,7 @@ impl QuizMutation {
             .format_err();
         }
         let quiz = Quiz::upsert(&mut conn, quiz).format_err()?;
+        refresh_assignment_max_score(&mut conn, new_page.document_id).format_err()?;
         Ok(quiz)
     }
 
@@ -124,6 +156,18 @@ impl QuizMutation {
         )
         .await?;
 
+        {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            let submission =
+                Submission::find_by_document(&mut conn, page.document_id).format_err()?;
+            if submission.map_or(false, |submission| submission.paused_at.is_some()) {
+                return Err(IkigaiError::new_bad_request(
+                    "Your test is paused by teacher",
+                ))
+                .format_err();
+            }
+        }
+
         data.user_id = get_user_id_from_ctx(ctx).await?;
         data.score = try_get_auto_score(quiz.quiz_type, quiz.answer_data, data.answer_data.clone());
 
@@ -132,6 +176,68 @@ impl QuizMutation {
         Ok(quiz_user_answer)
     }
 
+    // Teacher scores an answer of a submitted submission, none score gives it back to auto score
+    async fn quiz_score_answer(
+        &self,
+        ctx: &Context<'_>,
+        quiz_id: Uuid,
+        score: Option<f64>,
+        comment: Option<String>,
+    ) -> Result<QuizUserAnswer> {
+        let (quiz, submission, assignment) = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            let quiz = Quiz::find(&mut conn, quiz_id).format_err()?;
+            let page_content = PageContent::find(&mut conn, quiz.page_content_id).format_err()?;
+            let page = Page::find(&mut conn, page_content.page_id).format_err()?;
+            let submission = Submission::find_by_document(&mut conn, page.document_id)
+                .format_err()?
+                .ok_or_else(|| IkigaiError::new_bad_request("Quiz doesn't belong to a submission"))
+                .format_err()?;
+            let assignment =
+                Assignment::find_by_id(&mut conn, submission.assignment_id).format_err()?;
+            (quiz, submission, assignment)
+        };
+        document_quick_authorize(
+            ctx,
+            assignment.document_id,
+            DocumentActionPermission::ManageDocument,
+        )
+        .await?;
+
+        if submission.submit_at.is_none() {
+            return Err(IkigaiError::new_bad_request(
+                "Answer can only be scored after student submits",
+            ))
+            .format_err();
+        }
+        if score.map_or(false, |score| score < 0.0 || score > quiz.points) {
+            return Err(IkigaiError::new_bad_request(format!(
+                "Score must be between 0 and {}",
+                quiz.points
+            )))
+            .format_err();
+        }
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let answer = conn
+            .transaction::<_, IkigaiError, _>(|conn| {
+                let answer = QuizUserAnswer::update_manual_score(
+                    conn,
+                    quiz.id,
+                    submission.user_id,
+                    score,
+                    comment,
+                )?;
+                regrade_submission(conn, &assignment, &submission)?;
+                let needs_grading = submission_needs_grading(conn, &submission)?;
+                Submission::update_needs_grading(conn, submission.id, needs_grading)?;
+                Ok(answer)
+            })
+            .format_err()?;
+
+        Ok(answer)
+    }
+
     async fn quiz_generate_by_ai(
         &self,
         ctx: &Context<'_>,
@@ -140,22 +246,9 @@ impl QuizMutation {
     ) -> Result<AIGenerateQuizResponse> {
         let user = get_user_from_ctx(ctx).await?;
         let user_id = user.id;
-
-        // TODO: Replace usage logic by
-        let from = start_of_today();
-        let to = end_of_today();
-        let usage_today = {
+        {
             let mut conn = get_conn_from_ctx(ctx).await?;
-            AIHistorySession::count_by_time(&mut conn, user_id, from, to).format_err()?
-        };
-        let user_config = user.config();
-        if let Some(max_usage_per_day) = user_config.max_ai_usage_per_day {
-            if usage_today >= max_usage_per_day {
-                return Err(IkigaiError::new_bad_request(
-                    "You've reached maximum usage of a day. Want more - contact us via rodgers@ikigai.li",
-                ))
-                    .format_err();
-            }
+            check_ai_usage(&mut conn, &user).format_err()?;
         }
 
         let res = match quiz_type {
@@ -272,8 +365,139 @@ impl QuizMutation {
 
         let mut conn = get_conn_from_ctx(ctx).await?;
         let quizzes = Quiz::batch_insert(&mut conn, &quizzes).format_err()?;
+        refresh_assignment_max_score(&mut conn, page.document_id).format_err()?;
         Ok(quizzes)
     }
+
+    async fn quiz_bank_save(
+        &self,
+        ctx: &Context<'_>,
+        space_id: i32,
+        quiz_id: Uuid,
+        #[graphql(default)] difficulty: QuizDifficulty,
+        #[graphql(default)] tags: Vec<String>,
+    ) -> Result<BankQuiz> {
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+        let quiz = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            Quiz::find(&mut conn, quiz_id).format_err()?
+        };
+        document_quick_allowed_by_page_content(
+            ctx,
+            quiz.page_content_id,
+            DocumentActionPermission::EditDocument,
+        )
+        .await?;
+
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        let bank_quiz = BankQuiz::from_quiz(&quiz, space_id, user_id, difficulty);
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let bank_quiz = conn
+            .transaction::<_, IkigaiError, _>(|conn| {
+                let bank_quiz = BankQuiz::insert(conn, bank_quiz)?;
+                BankQuizTag::replace_all(conn, bank_quiz.id, tags)?;
+                Ok(bank_quiz)
+            })
+            .format_err()?;
+
+        Ok(bank_quiz)
+    }
+
+    async fn quiz_bank_update(
+        &self,
+        ctx: &Context<'_>,
+        bank_quiz_id: Uuid,
+        data: UpdateBankQuizData,
+        tags: Option<Vec<String>>,
+        // Also update copies of this quiz which are not submitted yet
+        #[graphql(default)] propagate: bool,
+    ) -> Result<BankQuiz> {
+        let bank_quiz = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            BankQuiz::find(&mut conn, bank_quiz_id).format_err()?
+        };
+        space_quick_authorize(
+            ctx,
+            bank_quiz.space_id,
+            SpaceActionPermission::ManageSpaceContent,
+        )
+        .await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let bank_quiz = conn
+            .transaction::<_, IkigaiError, _>(|conn| {
+                let bank_quiz = BankQuiz::update(conn, bank_quiz_id, data)?;
+                if let Some(tags) = tags {
+                    BankQuizTag::replace_all(conn, bank_quiz_id, tags)?;
+                }
+                Ok(bank_quiz)
+            })
+            .format_err()?;
+        if propagate {
+            propagate_bank_quiz(&mut conn, &bank_quiz).format_err()?;
+        }
+
+        Ok(bank_quiz)
+    }
+
+    async fn quiz_bank_remove(&self, ctx: &Context<'_>, bank_quiz_id: Uuid) -> Result<bool> {
+        let bank_quiz = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            BankQuiz::find(&mut conn, bank_quiz_id).format_err()?
+        };
+        space_quick_authorize(
+            ctx,
+            bank_quiz.space_id,
+            SpaceActionPermission::ManageSpaceContent,
+        )
+        .await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        BankQuiz::remove(&mut conn, bank_quiz_id).format_err()?;
+        Ok(true)
+    }
+
+    async fn quiz_bank_insert(
+        &self,
+        ctx: &Context<'_>,
+        bank_quiz_id: Uuid,
+        new_quiz_id: Uuid,
+        new_page_content_id: Uuid,
+    ) -> Result<Quiz> {
+        let (bank_quiz, new_page) = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            let bank_quiz = BankQuiz::find(&mut conn, bank_quiz_id).format_err()?;
+            let page_content = PageContent::find(&mut conn, new_page_content_id).format_err()?;
+            let new_page = Page::find(&mut conn, page_content.page_id).format_err()?;
+            (bank_quiz, new_page)
+        };
+        space_quick_authorize(
+            ctx,
+            bank_quiz.space_id,
+            SpaceActionPermission::ManageSpaceContent,
+        )
+        .await?;
+        document_quick_authorize(
+            ctx,
+            new_page.document_id,
+            DocumentActionPermission::EditDocument,
+        )
+        .await?;
+
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let existing_new_quiz = Quiz::find(&mut conn, new_quiz_id);
+        if existing_new_quiz.is_ok() {
+            return Err(IkigaiError::new_bad_request(
+                "Quiz block already existed in other pages",
+            ))
+            .format_err();
+        }
+        let quiz = bank_quiz.to_quiz(new_quiz_id, new_page_content_id, user_id);
+        let quiz = Quiz::upsert(&mut conn, quiz).format_err()?;
+        refresh_assignment_max_score(&mut conn, new_page.document_id).format_err()?;
+        Ok(quiz)
+    }
 }
 
 #[derive(Debug, Clone, InputObject)]
@@ -1,7 +1,7 @@
 use async_graphql::*;
 use uuid::Uuid;
 
-use crate::authorization::DocumentActionPermission;
+use crate::authorization::{DocumentActionPermission, SpaceActionPermission};
 use crate::db::*;
 use crate::error::IkigaiErrorExt;
 use crate::helper::*;
@@ -25,4 +25,17 @@ impl QuizQuery {
 
         Ok(quiz)
     }
+
+    async fn quiz_bank_search(
+        &self,
+        ctx: &Context<'_>,
+        space_id: i32,
+        #[graphql(default)] filter: BankQuizFilter,
+    ) -> Result<Vec<BankQuiz>> {
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let bank_quizzes = BankQuiz::search(&mut conn, space_id, &filter).format_err()?;
+        Ok(bank_quizzes)
+    }
 }
@@ -12,7 +12,8 @@ use crate::db::*;
 use crate::error::IkigaiErrorExt;
 use crate::graphql::data_loader::{FileById, IkigaiDataLoader, MembersByClassId, SpaceById};
 use crate::helper::{
-    document_quick_authorize, get_conn_from_ctx, get_public_user_from_loader, space_quick_authorize,
+    document_quick_authorize, get_conn_from_ctx, get_public_user_from_loader,
+    space_quick_authorize, GradebookRow,
 };
 
 #[ComplexObject]
@@ -111,3 +112,10 @@ impl SpaceInviteToken {
         get_public_user_from_loader(ctx, self.creator_id).await
     }
 }
+
+#[ComplexObject]
+impl GradebookRow {
+    async fn user(&self, ctx: &Context<'_>) -> Result<PublicUser> {
+        get_public_user_from_loader(ctx, self.user_id).await
+    }
+}
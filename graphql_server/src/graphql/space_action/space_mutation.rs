@@ -263,4 +263,36 @@ impl SpaceMutation {
 
         Ok(true)
     }
+
+    // Return download url of the exported file
+    async fn space_export_gradebook(
+        &self,
+        ctx: &Context<'_>,
+        space_id: i32,
+        format: GradebookExportFormat,
+    ) -> Result<String> {
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+
+        let (space, table) = {
+            let mut conn = get_conn_from_ctx(ctx).await?;
+            let space = Space::find_by_id(&mut conn, space_id).format_err()?;
+            let gradebook = get_space_gradebook(&mut conn, space_id).format_err()?;
+            let table = get_gradebook_table(&mut conn, &gradebook).format_err()?;
+            (space, table)
+        };
+        upload_gradebook(&space, &table, format).await.format_err()
+    }
+
+    async fn space_import_grades(
+        &self,
+        ctx: &Context<'_>,
+        space_id: i32,
+        content: String,
+    ) -> Result<Vec<Submission>> {
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let preview = preview_grade_import(&mut conn, space_id, &content).format_err()?;
+        apply_grade_import(&mut conn, preview).format_err()
+    }
 }
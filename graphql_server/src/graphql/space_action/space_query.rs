@@ -6,8 +6,9 @@ use crate::authorization::SpaceActionPermission;
 use crate::db::*;
 use crate::error::{IkigaiError, IkigaiErrorExt};
 use crate::helper::{
-    create_default_space, get_conn_from_ctx, get_space_allowed_permissions, get_user_id_from_ctx,
-    space_quick_authorize,
+    create_default_space, get_conn_from_ctx, get_space_allowed_permissions, get_space_gradebook,
+    get_user_id_from_ctx, preview_grade_import, space_quick_authorize, GradeImportPreview,
+    SpaceGradebook,
 };
 
 #[derive(Default)]
@@ -53,6 +54,24 @@ impl SpaceQuery {
             .format_err()?])
     }
 
+    async fn space_gradebook(&self, ctx: &Context<'_>, space_id: i32) -> Result<SpaceGradebook> {
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        get_space_gradebook(&mut conn, space_id).format_err()
+    }
+
+    // Validate grades csv before importing it
+    async fn space_preview_grade_import(
+        &self,
+        ctx: &Context<'_>,
+        space_id: i32,
+        content: String,
+    ) -> Result<GradeImportPreview> {
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        preview_grade_import(&mut conn, space_id, &content).format_err()
+    }
+
     async fn space_get_invite_tokens(
         &self,
         ctx: &Context<'_>,
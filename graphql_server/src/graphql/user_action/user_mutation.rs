@@ -3,13 +3,13 @@ use diesel::Connection;
 use uuid::Uuid;
 
 use crate::authentication_token::Claims;
-use crate::authorization::RubricActionPermission;
+use crate::authorization::{RubricActionPermission, SpaceActionPermission};
 use crate::db::*;
 use crate::error::{IkigaiError, IkigaiErrorExt};
 use crate::graphql::validator::Email;
 use crate::helper::{
     create_default_space, get_conn_from_ctx, get_user_from_ctx, get_user_id_from_ctx,
-    rubric_quick_authorize, send_start_space_magic_link,
+    rubric_quick_authorize, send_start_space_magic_link, space_quick_authorize,
 };
 use crate::service::google::verify_google_id_token;
 use crate::service::redis::Redis;
@@ -76,11 +76,14 @@ impl UserMutation {
     }
 
     async fn user_upsert_rubric(&self, ctx: &Context<'_>, mut rubric: Rubric) -> Result<Rubric> {
+        rubric.data.validate().map_err(|e| e.extend())?;
+        let user_id = get_user_id_from_ctx(ctx).await?;
         let existing_rubric = {
             let mut conn = get_conn_from_ctx(ctx).await?;
             Rubric::find_by_id(&mut conn, rubric.id)
         };
 
+        let mut is_new_version = false;
         if let Ok(existing_rubric) = existing_rubric {
             rubric_quick_authorize(
                 ctx,
@@ -88,18 +91,73 @@ impl UserMutation {
                 RubricActionPermission::ManageRubric,
             )
             .await?;
+
+            // Only creator can move or share the rubric
+            if existing_rubric.user_id != user_id {
+                rubric.space_id = existing_rubric.space_id;
+                rubric.share_mode = existing_rubric.share_mode;
+            }
+            rubric.user_id = existing_rubric.user_id;
+            rubric.version = existing_rubric.version;
+            if rubric.name != existing_rubric.name
+                || serde_json::to_value(&rubric.data)?
+                    != serde_json::to_value(&existing_rubric.data)?
+            {
+                rubric.version += 1;
+                is_new_version = true;
+            }
+        } else {
+            rubric.user_id = user_id;
+            rubric.version = 1;
+            is_new_version = true;
+        }
+
+        if let Some(space_id) = rubric.space_id {
+            space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
         }
 
-        let user_id = get_user_id_from_ctx(ctx).await?;
         let mut conn = get_conn_from_ctx(ctx).await?;
-        rubric.user_id = user_id;
-        let rubric = Rubric::upsert(&mut conn, rubric).format_err()?;
+        let rubric = conn
+            .transaction::<_, IkigaiError, _>(|conn| {
+                let rubric = Rubric::upsert(conn, rubric)?;
+                if is_new_version {
+                    RubricVersion::insert(conn, RubricVersion::new(&rubric, user_id))?;
+                }
+                Ok(rubric)
+            })
+            .format_err()?;
 
         Ok(rubric)
     }
 
+    async fn user_duplicate_rubric(
+        &self,
+        ctx: &Context<'_>,
+        rubric_id: Uuid,
+        space_id: Option<i32>,
+    ) -> Result<Rubric> {
+        rubric_quick_authorize(ctx, rubric_id, RubricActionPermission::ViewRubric).await?;
+        if let Some(space_id) = space_id {
+            space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+        }
+
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        let rubric = Rubric::find_by_id(&mut conn, rubric_id).format_err()?;
+        let new_rubric = rubric.duplicate(user_id, space_id);
+        let new_rubric = conn
+            .transaction::<_, IkigaiError, _>(|conn| {
+                let rubric = Rubric::upsert(conn, new_rubric)?;
+                RubricVersion::insert(conn, RubricVersion::new(&rubric, user_id))?;
+                Ok(rubric)
+            })
+            .format_err()?;
+
+        Ok(new_rubric)
+    }
+
     async fn user_remove_rubric(&self, ctx: &Context<'_>, rubric_id: Uuid) -> Result<bool> {
-        rubric_quick_authorize(ctx, rubric_id, RubricActionPermission::ManageRubric).await?;
+        rubric_quick_authorize(ctx, rubric_id, RubricActionPermission::DeleteRubric).await?;
 
         let mut conn = get_conn_from_ctx(ctx).await?;
         Rubric::remove(&mut conn, rubric_id).format_err()?;
@@ -1,9 +1,13 @@
 use async_graphql::*;
 use uuid::Uuid;
 
+use crate::authorization::{RubricActionPermission, SpaceActionPermission};
 use crate::db::*;
 use crate::error::IkigaiErrorExt;
-use crate::helper::{get_conn_from_ctx, get_user_from_ctx, get_user_id_from_ctx};
+use crate::helper::{
+    get_conn_from_ctx, get_user_from_ctx, get_user_id_from_ctx, rubric_quick_authorize,
+    space_quick_authorize,
+};
 
 #[derive(Default)]
 pub struct UserQuery;
@@ -27,6 +31,27 @@ impl UserQuery {
         Rubric::find_all_by_user(&mut conn, user_id).format_err()
     }
 
+    async fn user_get_space_rubrics(
+        &self,
+        ctx: &Context<'_>,
+        space_id: i32,
+    ) -> Result<Vec<Rubric>> {
+        space_quick_authorize(ctx, space_id, SpaceActionPermission::ManageSpaceContent).await?;
+        let user_id = get_user_id_from_ctx(ctx).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        Rubric::find_all_by_space(&mut conn, space_id, user_id).format_err()
+    }
+
+    async fn user_get_rubric_versions(
+        &self,
+        ctx: &Context<'_>,
+        rubric_id: Uuid,
+    ) -> Result<Vec<RubricVersion>> {
+        rubric_quick_authorize(ctx, rubric_id, RubricActionPermission::ViewRubric).await?;
+        let mut conn = get_conn_from_ctx(ctx).await?;
+        RubricVersion::find_all_by_rubric(&mut conn, rubric_id).format_err()
+    }
+
     async fn user_check_document(
         &self,
         ctx: &Context<'_>,
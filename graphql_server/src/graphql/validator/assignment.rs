@@ -0,0 +1,83 @@
+use async_graphql::{CustomValidator, InputValueError};
+
+use crate::db::{AssignmentAccommodation, UpdateAssignmentData};
+
+pub struct AssignmentSettingValidator;
+
+impl CustomValidator<UpdateAssignmentData> for AssignmentSettingValidator {
+    fn check(
+        &self,
+        value: &UpdateAssignmentData,
+    ) -> Result<(), InputValueError<UpdateAssignmentData>> {
+        check_assignment_setting(value).map_err(InputValueError::custom)
+    }
+}
+
+pub fn check_assignment_setting(data: &UpdateAssignmentData) -> Result<(), String> {
+    if let (Some(available_from), Some(due_at)) = (data.available_from, data.due_at) {
+        if available_from >= due_at {
+            return Err("Due time must be after available time".into());
+        }
+    }
+
+    if let (Some(available_from), Some(close_at)) = (data.available_from, data.close_at) {
+        if available_from >= close_at {
+            return Err("Close time must be after available time".into());
+        }
+    }
+
+    if let (Some(due_at), Some(close_at)) = (data.due_at, data.close_at) {
+        if due_at > close_at {
+            return Err("Close time cannot be before due time".into());
+        }
+    }
+
+    if let Some(negative_marking) = data.negative_marking {
+        if !(0.0..=1.0).contains(&negative_marking) {
+            return Err("Negative marking must be between 0 and 1".into());
+        }
+    }
+
+    if let Some(late_penalty) = data.late_penalty {
+        if !(0.0..=1.0).contains(&late_penalty) {
+            return Err("Late penalty must be between 0 and 1".into());
+        }
+    }
+
+    Ok(())
+}
+
+pub struct AssignmentAccommodationValidator;
+
+impl CustomValidator<AssignmentAccommodation> for AssignmentAccommodationValidator {
+    fn check(
+        &self,
+        value: &AssignmentAccommodation,
+    ) -> Result<(), InputValueError<AssignmentAccommodation>> {
+        check_assignment_accommodation(value).map_err(InputValueError::custom)
+    }
+}
+
+pub fn check_assignment_accommodation(data: &AssignmentAccommodation) -> Result<(), String> {
+    if data
+        .test_duration
+        .map_or(false, |test_duration| test_duration <= 0)
+    {
+        return Err("Test duration must be positive".into());
+    }
+
+    if data
+        .max_number_of_attempt
+        .map_or(false, |max_number_of_attempt| max_number_of_attempt <= 0)
+    {
+        return Err("Max number of attempt must be positive".into());
+    }
+
+    if let (Some(due_at), Some(close_at)) = (data.due_at, data.close_at) {
+        if due_at > close_at {
+            return Err("Close time cannot be before due time".into());
+        }
+    }
+
+    Ok(())
+}
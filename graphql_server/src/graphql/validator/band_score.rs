@@ -0,0 +1,46 @@
+use async_graphql::{CustomValidator, InputValueError};
+use itertools::Itertools;
+
+use crate::db::BandScoreRanges;
+
+pub struct BandScoreRangesValidator;
+
+impl CustomValidator<BandScoreRanges> for BandScoreRangesValidator {
+    fn check(&self, value: &BandScoreRanges) -> Result<(), InputValueError<BandScoreRanges>> {
+        check_band_score_ranges(value).map_err(InputValueError::custom)
+    }
+}
+
+pub fn check_band_score_ranges(ranges: &BandScoreRanges) -> Result<(), String> {
+    if ranges.items.is_empty() {
+        return Err("Band score needs at least one range".into());
+    }
+
+    for range in ranges.items.iter() {
+        if range.from > range.to {
+            return Err(format!(
+                "Range {} -> {} has from greater than to",
+                range.from, range.to
+            ));
+        }
+    }
+
+    let sorted_ranges = ranges.items.iter().sorted_by_key(|range| range.from);
+    for (previous, next) in sorted_ranges.tuple_windows() {
+        if next.from <= previous.to {
+            return Err(format!(
+                "Range {} -> {} overlaps range {} -> {}",
+                next.from, next.to, previous.from, previous.to
+            ));
+        }
+
+        if next.from > previous.to + 1 {
+            return Err(format!(
+                "There is a gap between range {} -> {} and range {} -> {}",
+                previous.from, previous.to, next.from, next.to
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -1,3 +1,7 @@
+pub mod assignment;
+pub mod band_score;
 pub mod id;
 
+pub use assignment::*;
+pub use band_score::*;
 pub use id::*;
@@ -4,6 +4,7 @@ use uuid::Uuid;
 use crate::db::Document;
 use crate::db::*;
 use crate::error::IkigaiError;
+use crate::helper::find_document_quizzes;
 use crate::util::get_now_as_secs;
 
 #[derive(Debug, Clone, Builder)]
@@ -23,6 +24,12 @@ pub struct DocumentCloneConfig {
     pub clone_to_replace_document_id: Option<Uuid>,
     #[builder(default = "true")]
     pub keep_document_type: bool,
+    // Quizzes which are left out of the cloned document
+    #[builder(default)]
+    pub excluded_quiz_ids: Vec<Uuid>,
+    // Shuffle options of choice quizzes in the cloned document
+    #[builder(default)]
+    pub shuffle_options_seed: Option<i64>,
 }
 
 impl Document {
@@ -59,7 +66,7 @@ impl Document {
         // Step 1: Clone pages of document
         let pages = Page::find_all_by_document_id(conn, self.id)?;
         for page in pages {
-            page.deep_clone(conn, &new_document)?;
+            page.deep_clone(conn, &new_document, &config)?;
         }
 
         // Step 2: Document Type
@@ -67,6 +74,15 @@ impl Document {
             if let Ok(Some(assignment)) = Assignment::find_by_document(conn, self.id) {
                 let mut new_assignment = NewAssignment::from(assignment);
                 new_assignment.document_id = new_document.id;
+                if !new_assignment.question_pools.is_empty() {
+                    let new_quiz_ids = find_document_quizzes(conn, new_document.id)?
+                        .into_iter()
+                        .filter_map(|quiz| quiz.original_quiz_id.map(|id| (id, quiz.id)))
+                        .collect();
+                    new_assignment.question_pools = new_assignment
+                        .question_pools
+                        .replace_quiz_ids(&new_quiz_ids);
+                }
                 Assignment::insert(conn, new_assignment)?;
             }
 
@@ -107,6 +123,7 @@ impl Page {
         &self,
         conn: &mut PgConnection,
         new_document: &Document,
+        config: &DocumentCloneConfig,
     ) -> Result<Self, IkigaiError> {
         let mut this = self.clone();
         this.id = Uuid::new_v4();
@@ -119,7 +136,7 @@ impl Page {
 
         let page_contents = PageContent::find_all_by_page(conn, self.id)?;
         for page_content in page_contents {
-            page_content.deep_clone(conn, &new_page, new_document.creator_id)?;
+            page_content.deep_clone(conn, &new_page, new_document.creator_id, config)?;
         }
 
         Ok(new_page)
@@ -132,16 +149,27 @@ impl PageContent {
         conn: &mut PgConnection,
         new_page: &Page,
         creator_id: i32,
+        config: &DocumentCloneConfig,
     ) -> Result<Self, IkigaiError> {
         let new_content =
             PageContent::new(Uuid::new_v4(), new_page.id, self.index, self.body.clone());
         let mut new_page_content = PageContent::upsert(conn, new_content)?;
 
         let mut new_content = new_page_content.get_json_content();
+        new_content.remove_quiz_blocks(&config.excluded_quiz_ids);
 
         let quizzes = Quiz::find_all_by_page_contents(conn, &vec![self.id])?;
         for quiz in quizzes {
-            if let Ok(new_quiz) = quiz.deep_clone(conn, &new_page_content, creator_id) {
+            if config.excluded_quiz_ids.contains(&quiz.id) {
+                continue;
+            }
+
+            if let Ok(new_quiz) = quiz.deep_clone(
+                conn,
+                &new_page_content,
+                creator_id,
+                config.shuffle_options_seed,
+            ) {
                 new_content.replace_block_id(
                     new_quiz.quiz_type.block_name(),
                     new_quiz.quiz_type.id_name(),
@@ -162,12 +190,16 @@ impl Quiz {
         conn: &mut PgConnection,
         new_page_content: &PageContent,
         creator_id: i32,
+        shuffle_options_seed: Option<i64>,
     ) -> Result<Self, IkigaiError> {
         let mut new_quiz = self.clone();
         new_quiz.id = Uuid::new_v4();
         new_quiz.page_content_id = new_page_content.id;
         new_quiz.creator_id = creator_id;
         new_quiz.original_quiz_id = Some(self.id);
+        if let Some(seed) = shuffle_options_seed {
+            new_quiz.shuffle_options(seed);
+        }
         Ok(Quiz::upsert(conn, new_quiz)?)
     }
 }
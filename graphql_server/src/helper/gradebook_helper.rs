@@ -0,0 +1,625 @@
+use std::collections::{HashMap, HashSet};
+
+use aws_sdk_s3::types::ByteStream;
+use diesel::{Connection, PgConnection};
+use itertools::Itertools;
+use uuid::Uuid;
+
+use crate::db::*;
+use crate::error::IkigaiError;
+use crate::helper::resolve_assignment_grade;
+use crate::notification_center::send_notifications;
+use crate::service::Storage;
+use crate::util::get_now_as_secs;
+use crate::util::xlsx_util::{write_xlsx, XlsxCell};
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct SpaceGradebook {
+    pub space_id: i32,
+    pub assignments: Vec<GradebookAssignment>,
+    pub rows: Vec<GradebookRow>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct GradebookAssignment {
+    pub assignment_id: i32,
+    pub document_id: Uuid,
+    pub title: String,
+    pub max_score: Option<f64>,
+    pub grade_policy: GradePolicy,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(complex)]
+pub struct GradebookRow {
+    pub user_id: i32,
+    // Same order as assignments of the gradebook
+    pub cells: Vec<GradebookCell>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct GradebookCell {
+    pub assignment_id: i32,
+    pub final_grade: Option<f64>,
+    // Status of the submission deciding the grade, or the latest one. None if not started
+    pub status: Option<SubmissionStatus>,
+    pub attempt_count: i32,
+    pub is_late: bool,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct GradeImportPreview {
+    pub rows: Vec<GradeImportRow>,
+    pub number_of_errors: i32,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct GradeImportRow {
+    // Line number in the csv file, header is line 1
+    pub line: i32,
+    pub email: String,
+    pub assignment: String,
+    pub grade: Option<f64>,
+    pub feedback: Option<String>,
+    pub user_id: Option<i32>,
+    pub assignment_id: Option<i32>,
+    // Latest submitted attempt of the student, which will be graded
+    pub submission_id: Option<i32>,
+    pub current_grade: Option<f64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum GradebookExportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl GradebookExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GradebookExportFormat::Csv => "csv",
+            GradebookExportFormat::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GradebookExportFormat::Csv => "text/csv",
+            GradebookExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+}
+
+pub fn get_space_gradebook(
+    conn: &mut PgConnection,
+    space_id: i32,
+) -> Result<SpaceGradebook, IkigaiError> {
+    let documents = Document::find_all_non_submission_in_space(conn, space_id)?;
+    let document_ids = documents.iter().map(|document| document.id).collect();
+    let assignments = Assignment::find_all_by_documents(conn, &document_ids)?;
+    let submissions = Submission::find_all_by_assignments(
+        conn,
+        assignments.iter().map(|assignment| assignment.id).collect(),
+    )?;
+    let students =
+        SpaceMember::find_all_space_members_by_role_and_class(conn, space_id, Role::Student)?;
+
+    let documents: HashMap<Uuid, Document> = documents
+        .into_iter()
+        .map(|document| (document.id, document))
+        .collect();
+    let assignments: Vec<(Assignment, &Document)> = assignments
+        .into_iter()
+        .filter_map(|assignment| {
+            let document = documents.get(&assignment.document_id)?;
+            Some((assignment, document))
+        })
+        .sorted_by_key(|(_, document)| document.index)
+        .collect();
+
+    let mut submissions_by_student: HashMap<(i32, i32), Vec<Submission>> = HashMap::new();
+    for submission in submissions {
+        submissions_by_student
+            .entry((submission.assignment_id, submission.user_id))
+            .or_default()
+            .push(submission);
+    }
+
+    let mut rows = vec![];
+    for student in students {
+        let mut cells = vec![];
+        for (assignment, _) in assignments.iter() {
+            let submissions = submissions_by_student
+                .get(&(assignment.id, student.user_id))
+                .cloned()
+                .unwrap_or_default();
+            let grade = resolve_assignment_grade(assignment, student.user_id, &submissions, false);
+            let deciding_submission = submissions
+                .iter()
+                .find(|submission| Some(submission.id) == grade.submission_id)
+                .or_else(|| {
+                    submissions
+                        .iter()
+                        .max_by_key(|submission| submission.attempt_number)
+                });
+
+            cells.push(GradebookCell {
+                assignment_id: assignment.id,
+                final_grade: grade.grade,
+                status: deciding_submission.map(|submission| submission.submission_status()),
+                attempt_count: submissions.len() as i32,
+                is_late: deciding_submission.map_or(false, |submission| submission.is_late),
+            });
+        }
+        rows.push(GradebookRow {
+            user_id: student.user_id,
+            cells,
+        });
+    }
+
+    Ok(SpaceGradebook {
+        space_id,
+        assignments: assignments
+            .into_iter()
+            .map(|(assignment, document)| GradebookAssignment {
+                assignment_id: assignment.id,
+                document_id: document.id,
+                title: document.title.clone(),
+                max_score: assignment.max_score,
+                grade_policy: assignment.grade_policy,
+            })
+            .collect(),
+        rows,
+    })
+}
+
+// Rows of exported file: one student per row, and per assignment the final grade,
+// status, attempt count and late flag of the student
+pub fn get_gradebook_table(
+    conn: &mut PgConnection,
+    gradebook: &SpaceGradebook,
+) -> Result<Vec<Vec<XlsxCell>>, IkigaiError> {
+    let user_ids = gradebook.rows.iter().map(|row| row.user_id).collect();
+    let users: HashMap<i32, User> = User::find_by_ids(conn, &user_ids)?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+    Ok(build_gradebook_table(gradebook, &users))
+}
+
+fn build_gradebook_table(
+    gradebook: &SpaceGradebook,
+    users: &HashMap<i32, User>,
+) -> Vec<Vec<XlsxCell>> {
+    let mut header = vec![
+        XlsxCell::Text("First Name".to_string()),
+        XlsxCell::Text("Last Name".to_string()),
+        XlsxCell::Text("Email".to_string()),
+    ];
+    for assignment in gradebook.assignments.iter() {
+        header.push(XlsxCell::Text(assignment.title.clone()));
+        header.push(XlsxCell::Text(format!("{} - Status", assignment.title)));
+        header.push(XlsxCell::Text(format!("{} - Attempts", assignment.title)));
+        header.push(XlsxCell::Text(format!("{} - Late", assignment.title)));
+    }
+
+    let mut table = vec![header];
+    for row in gradebook.rows.iter() {
+        let Some(user) = users.get(&row.user_id) else {
+            continue;
+        };
+        let mut line = vec![
+            XlsxCell::Text(user.first_name.clone()),
+            XlsxCell::Text(user.last_name.clone()),
+            XlsxCell::Text(user.email.clone()),
+        ];
+        for cell in row.cells.iter() {
+            line.push(match cell.final_grade {
+                Some(grade) => XlsxCell::Number(grade),
+                None => XlsxCell::Empty,
+            });
+            line.push(match cell.status {
+                Some(status) => XlsxCell::Text(get_status_label(status).to_string()),
+                None => XlsxCell::Empty,
+            });
+            line.push(XlsxCell::Number(cell.attempt_count as f64));
+            line.push(XlsxCell::Text(
+                if cell.is_late { "Yes" } else { "No" }.to_string(),
+            ));
+        }
+        table.push(line);
+    }
+
+    table
+}
+
+fn get_status_label(status: SubmissionStatus) -> &'static str {
+    match status {
+        SubmissionStatus::InDoing => "In Doing",
+        SubmissionStatus::Submitted => "Submitted",
+        SubmissionStatus::Graded => "Graded",
+    }
+}
+
+fn write_csv(table: &[Vec<XlsxCell>]) -> Result<Vec<u8>, IkigaiError> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for line in table {
+        let record = line.iter().map(|cell| match cell {
+            XlsxCell::Text(text) => escape_csv_formula(text),
+            XlsxCell::Number(number) if number.is_finite() => number.to_string(),
+            XlsxCell::Number(_) | XlsxCell::Empty => String::new(),
+        });
+        writer.write_record(record)?;
+    }
+    writer.flush()?;
+    writer
+        .into_inner()
+        .map_err(|_| IkigaiError::InternalServerError)
+}
+
+// Spreadsheet apps run a text starting with these characters as a formula,
+// e.g. a student named "=HYPERLINK(...)", so it's kept as text by a leading quote.
+fn escape_csv_formula(text: &str) -> String {
+    if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{text}")
+    } else {
+        text.to_string()
+    }
+}
+
+// Upload the exported gradebook and return its download url
+pub async fn upload_gradebook(
+    space: &Space,
+    table: &[Vec<XlsxCell>],
+    format: GradebookExportFormat,
+) -> Result<String, IkigaiError> {
+    let data = match format {
+        GradebookExportFormat::Csv => write_csv(table)?,
+        GradebookExportFormat::Xlsx => write_xlsx("Gradebook", table)?,
+    };
+
+    let key = format!("space_{}/gradebook/{}", space.id, Uuid::new_v4());
+    let file_name = format!("{} - Gradebook.{}", space.name, format.extension());
+    let storage = Storage::from_env_config();
+    storage
+        .upload_bytes(&key, format.content_type(), ByteStream::from(data))
+        .await?;
+    storage.get_download_url(&key, 3600, Some(file_name)).await
+}
+
+// Csv columns: email, assignment (id or title), grade and optional feedback
+pub fn preview_grade_import(
+    conn: &mut PgConnection,
+    space_id: i32,
+    content: &str,
+) -> Result<GradeImportPreview, IkigaiError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|_| IkigaiError::new_bad_request("Cannot read header of the csv file"))?
+        .iter()
+        .map(|header| header.to_lowercase())
+        .collect();
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let (Some(email_col), Some(assignment_col), Some(grade_col)) =
+        (column("email"), column("assignment"), column("grade"))
+    else {
+        return Err(IkigaiError::new_bad_request(
+            "The csv file must have email, assignment and grade columns",
+        ));
+    };
+    let feedback_col = column("feedback");
+
+    let mut records = vec![];
+    for record in reader.records() {
+        let record = record
+            .map_err(|e| IkigaiError::new_bad_request(format!("Cannot read the csv file: {e}")))?;
+        let get = |col: usize| record.get(col).unwrap_or_default().to_string();
+        records.push((
+            get(email_col),
+            get(assignment_col),
+            get(grade_col),
+            feedback_col
+                .map(get)
+                .filter(|feedback| !feedback.is_empty()),
+        ));
+    }
+
+    let student_ids: HashSet<i32> =
+        SpaceMember::find_all_space_members_by_role_and_class(conn, space_id, Role::Student)?
+            .into_iter()
+            .map(|member| member.user_id)
+            .collect();
+    let emails = records
+        .iter()
+        .map(|(email, _, _, _)| email.to_lowercase())
+        .collect();
+    let users: HashMap<String, User> = User::find_by_emails(conn, &emails)?
+        .into_iter()
+        .map(|user| (user.email.to_lowercase(), user))
+        .collect();
+    let documents = Document::find_all_non_submission_in_space(conn, space_id)?;
+    let document_ids = documents.iter().map(|document| document.id).collect();
+    let assignments = Assignment::find_all_by_documents(conn, &document_ids)?;
+    let assignment_titles: HashMap<i32, String> = assignments
+        .iter()
+        .filter_map(|assignment| {
+            let document = documents
+                .iter()
+                .find(|document| document.id == assignment.document_id)?;
+            Some((assignment.id, document.title.trim().to_lowercase()))
+        })
+        .collect();
+    let submissions = Submission::find_all_by_assignments(
+        conn,
+        assignments.iter().map(|assignment| assignment.id).collect(),
+    )?;
+
+    let mut max_final_grades: HashMap<i32, Option<f64>> = HashMap::new();
+    for assignment in assignments.iter() {
+        max_final_grades.insert(assignment.id, get_max_final_grade(conn, assignment)?);
+    }
+
+    let mut graded_lines: HashMap<i32, i32> = HashMap::new();
+    let mut rows = vec![];
+    for (index, (email, assignment_name, grade, feedback)) in records.into_iter().enumerate() {
+        let mut row = GradeImportRow {
+            line: index as i32 + 2,
+            email,
+            assignment: assignment_name,
+            grade: None,
+            feedback,
+            user_id: None,
+            assignment_id: None,
+            submission_id: None,
+            current_grade: None,
+            error: None,
+        };
+
+        let user = users
+            .get(&row.email.to_lowercase())
+            .filter(|user| student_ids.contains(&user.id));
+        let matched_assignments = assignments
+            .iter()
+            .filter(|assignment| match row.assignment.parse::<i32>() {
+                Ok(assignment_id) => assignment.id == assignment_id,
+                Err(_) => {
+                    assignment_titles.get(&assignment.id) == Some(&row.assignment.to_lowercase())
+                }
+            })
+            .collect_vec();
+        row.grade = grade.parse::<f64>().ok().filter(|grade| grade.is_finite());
+
+        let error = match (user, matched_assignments.as_slice()) {
+            (None, _) => Some("Student is not found in this space".to_string()),
+            (_, []) => Some("Assignment is not found in this space".to_string()),
+            (_, [_, _, ..]) => Some("Assignment title is ambiguous, use its id".to_string()),
+            (Some(user), [assignment]) => {
+                row.user_id = Some(user.id);
+                row.assignment_id = Some(assignment.id);
+                let submission = submissions
+                    .iter()
+                    .filter(|submission| {
+                        submission.assignment_id == assignment.id
+                            && submission.user_id == user.id
+                            && submission.submit_at.is_some()
+                    })
+                    .max_by_key(|submission| submission.attempt_number);
+                row.submission_id = submission.map(|submission| submission.id);
+                row.current_grade = submission.and_then(|submission| submission.final_grade);
+                let max_final_grade = max_final_grades.get(&assignment.id).copied().flatten();
+                match (submission, row.grade) {
+                    (None, _) => Some("Student has no submitted submission".to_string()),
+                    (_, None) => Some(format!("Invalid grade \"{grade}\"")),
+                    (_, Some(grade)) if grade < 0.0 => Some("Grade cannot be negative".to_string()),
+                    (_, Some(grade)) if max_final_grade.map_or(false, |max| grade > max) => {
+                        Some(format!(
+                            "Grade is greater than max score {}",
+                            max_final_grade.unwrap_or_default()
+                        ))
+                    }
+                    (Some(submission), _) => graded_lines
+                        .insert(submission.id, row.line)
+                        .map(|line| format!("Submission is already graded at line {line}")),
+                }
+            }
+        };
+        row.error = error;
+        rows.push(row);
+    }
+
+    let number_of_errors = rows.iter().filter(|row| row.error.is_some()).count();
+    Ok(GradeImportPreview {
+        rows,
+        number_of_errors: number_of_errors as i32,
+    })
+}
+
+// Final grades are on the scale of the grade method: rubric scores, band scores
+// or points of the assignment quizzes. None if the scale is unknown.
+fn get_max_final_grade(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+) -> Result<Option<f64>, IkigaiError> {
+    if assignment.grade_method == GradeMethod::Rubric {
+        let Some(rubric_id) = assignment.grade_by_rubric_id else {
+            return Ok(None);
+        };
+        let rubric = Rubric::find_by_id(conn, rubric_id)?;
+        return Ok(Some(rubric.data.max_rubric_score()));
+    }
+
+    if let Some(band_score_id) = assignment.band_score_id {
+        let band_score = BandScore::find(conn, band_score_id)?;
+        return Ok(band_score.range.max_score());
+    }
+
+    // Assignment without quizzes is graded on the teacher's own scale
+    Ok(assignment.max_score.filter(|max_score| *max_score > 0.0))
+}
+
+// Grade all submissions of the import, nothing is applied in case of any invalid row
+pub fn apply_grade_import(
+    conn: &mut PgConnection,
+    preview: GradeImportPreview,
+) -> Result<Vec<Submission>, IkigaiError> {
+    if preview.number_of_errors > 0 {
+        return Err(IkigaiError::new_bad_request(format!(
+            "Cannot import grades, {} rows are invalid",
+            preview.number_of_errors
+        )));
+    }
+
+    let submissions = conn.transaction::<_, IkigaiError, _>(|conn| {
+        let mut submissions = vec![];
+        for row in preview.rows {
+            let Some(submission_id) = row.submission_id else {
+                continue;
+            };
+            let submission = Submission::find_by_id(conn, submission_id)?;
+            let grade_data = GradeSubmissionData {
+                final_grade: row.grade,
+                feedback: row.feedback.or(submission.feedback),
+                feedback_at: None,
+                allow_for_student_view_answer: true,
+                updated_at: get_now_as_secs(),
+            };
+            submissions.push(Submission::grade_submission(
+                conn,
+                submission_id,
+                grade_data,
+            )?);
+        }
+        Ok(submissions)
+    })?;
+
+    let documents: HashMap<Uuid, Document> = Document::find_by_ids(
+        conn,
+        submissions
+            .iter()
+            .map(|submission| submission.document_id)
+            .collect(),
+    )?
+    .into_iter()
+    .map(|document| (document.id, document))
+    .collect();
+    let notifications = submissions
+        .iter()
+        .map(|submission| {
+            let notification =
+                Notification::new_feedback_submission_notification(FeedbackSubmissionContext {
+                    document_submission_id: submission.document_id,
+                    submission_name: documents
+                        .get(&submission.document_id)
+                        .map(|document| document.title.clone())
+                        .unwrap_or_default(),
+                });
+            (notification, vec![submission.user_id])
+        })
+        .collect_vec();
+    Notification::insert_many(
+        conn,
+        notifications
+            .iter()
+            .map(|(notification, _)| notification.clone())
+            .collect(),
+    )?;
+    send_notifications(conn, notifications)?;
+
+    Ok(submissions)
+}
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+
+    use super::*;
+
+    #[test]
+    fn gradebook_table_has_status_attempts_and_late_columns() {
+        let cell = |assignment_id, final_grade, status, attempt_count, is_late| GradebookCell {
+            assignment_id,
+            final_grade,
+            status,
+            attempt_count,
+            is_late,
+        };
+        let gradebook = SpaceGradebook {
+            space_id: 1,
+            assignments: vec![GradebookAssignment {
+                assignment_id: 10,
+                document_id: Uuid::new_v4(),
+                title: "Essay".to_string(),
+                max_score: Some(10.0),
+                grade_policy: GradePolicy::Latest,
+            }],
+            rows: vec![
+                GradebookRow {
+                    user_id: 1,
+                    cells: vec![cell(10, Some(8.5), Some(SubmissionStatus::Graded), 2, true)],
+                },
+                GradebookRow {
+                    user_id: 2,
+                    cells: vec![cell(10, None, None, 0, false)],
+                },
+            ],
+        };
+        let users = vec![(1, "Anna"), (2, "Bob")]
+            .into_iter()
+            .map(|(id, first_name)| {
+                let user = User {
+                    id,
+                    email: format!("{}@ikigai.li", first_name.to_lowercase()),
+                    first_name: first_name.to_string(),
+                    last_name: "Lee".to_string(),
+                    updated_at: 0,
+                    created_at: 0,
+                    avatar_file_id: None,
+                    account_type: AccountType::Normal,
+                };
+                (id, user)
+            })
+            .collect();
+
+        let csv = String::from_utf8(write_csv(&build_gradebook_table(&gradebook, &users)).unwrap())
+            .unwrap();
+        assert_eq!(
+            csv,
+            "First Name,Last Name,Email,Essay,Essay - Status,Essay - Attempts,Essay - Late\n\
+             Anna,Lee,anna@ikigai.li,8.5,Graded,2,Yes\n\
+             Bob,Lee,bob@ikigai.li,,,0,No\n"
+        );
+    }
+
+    #[test]
+    fn write_csv_escapes_formulas() {
+        let table = vec![
+            vec![
+                XlsxCell::Text("=HYPERLINK(\"http://x\")".to_string()),
+                XlsxCell::Text("+1".to_string()),
+                XlsxCell::Text("-1".to_string()),
+                XlsxCell::Text("@SUM(A1)".to_string()),
+                XlsxCell::Text("Anna-Marie".to_string()),
+            ],
+            vec![
+                XlsxCell::Number(-1.5),
+                XlsxCell::Number(f64::NAN),
+                XlsxCell::Number(f64::INFINITY),
+                XlsxCell::Empty,
+                XlsxCell::Number(10.0),
+            ],
+        ];
+        let csv = String::from_utf8(write_csv(&table).unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "\"'=HYPERLINK(\"\"http://x\"\")\",'+1,'-1,'@SUM(A1),Anna-Marie\n-1.5,,,,10\n"
+        );
+    }
+}
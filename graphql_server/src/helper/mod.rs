@@ -1,8 +1,18 @@
 pub mod document_helper;
+pub mod gradebook_helper;
+pub mod page_content_version_helper;
+pub mod quiz_bank_helper;
+pub mod quiz_statistic_helper;
+pub mod rubric_grading_helper;
 pub mod submission_helper;
 
 pub use crate::authorization::authorize_helper::*;
 pub use document_helper::*;
+pub use gradebook_helper::*;
+pub use page_content_version_helper::*;
+pub use quiz_bank_helper::*;
+pub use quiz_statistic_helper::*;
+pub use rubric_grading_helper::*;
 pub use submission_helper::*;
 
 use async_graphql::dataloader::DataLoader;
@@ -17,7 +27,7 @@ use crate::mailer::Mailer;
 use crate::service::redis::Redis;
 use crate::service::Storage;
 use crate::util::url_util::{format_document_magic_link, format_start_space_magic_link};
-use crate::util::{generate_otp, get_now_as_secs};
+use crate::util::{end_of_today, generate_otp, get_now_as_secs, start_of_today};
 
 pub async fn get_public_user_from_loader(ctx: &Context<'_>, user_id: i32) -> Result<PublicUser> {
     let loader = ctx.data_unchecked::<DataLoader<IkigaiDataLoader>>();
@@ -156,3 +166,17 @@ pub fn create_default_space(conn: &mut PgConnection, user_id: i32) -> Result<Spa
         Ok(space)
     })
 }
+
+pub fn check_ai_usage(conn: &mut PgConnection, user: &User) -> Result<(), IkigaiError> {
+    let usage_today =
+        AIHistorySession::count_by_time(conn, user.id, start_of_today(), end_of_today())?;
+    if let Some(max_usage_per_day) = user.config().max_ai_usage_per_day {
+        if usage_today >= max_usage_per_day {
+            return Err(IkigaiError::new_bad_request(
+                "You've reached maximum usage of a day. Want more - contact us via rodgers@ikigai.li",
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,378 @@
+use diesel::{Connection, PgConnection};
+use itertools::Itertools;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::db::*;
+use crate::error::IkigaiError;
+use crate::helper::{add_regrade_quiz_job, refresh_assignment_max_score};
+use crate::util::get_now_as_secs;
+
+// Editing keeps at most one automatic version of a page content in this period
+const AUTO_VERSION_INTERVAL_SECONDS: i64 = 300;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Enum)]
+pub enum BlockChangeType {
+    Unchanged,
+    Added,
+    Removed,
+    Modified,
+}
+
+// Change of a top level block of the tiptap content
+#[derive(Debug, Clone, SimpleObject)]
+pub struct PageContentBlockDiff {
+    pub change_type: BlockChangeType,
+    pub old_index: Option<i32>,
+    pub new_index: Option<i32>,
+    pub old_block: Option<Value>,
+    pub new_block: Option<Value>,
+}
+
+// Quizzes placed in the page content
+pub fn find_page_content_quizzes(
+    conn: &mut PgConnection,
+    page_content: &PageContent,
+) -> Result<Vec<Quiz>, IkigaiError> {
+    let quiz_block_ids = page_content.get_json_content().find_quiz_block_ids();
+    let quizzes = Quiz::find_all_by_page_contents(conn, &vec![page_content.id])?;
+    Ok(quizzes
+        .into_iter()
+        .filter(|quiz| quiz_block_ids.contains(&quiz.id))
+        .collect())
+}
+
+pub fn create_page_content_version(
+    conn: &mut PgConnection,
+    page_content: &PageContent,
+    name: Option<String>,
+    is_auto: bool,
+    creator_id: i32,
+) -> Result<PageContentVersion, IkigaiError> {
+    let quizzes = find_page_content_quizzes(conn, page_content)?;
+    let version = PageContentVersion::new(page_content, &quizzes, name, is_auto, creator_id);
+    Ok(PageContentVersion::insert(conn, version)?)
+}
+
+// Keep the content before it is overwritten, unless a version was created recently
+// or the content is not changed since the latest version.
+pub fn auto_create_page_content_version(
+    conn: &mut PgConnection,
+    page_content: &PageContent,
+    creator_id: i32,
+) -> Result<Option<PageContentVersion>, IkigaiError> {
+    if let Some(latest) = PageContentVersion::find_latest(conn, page_content.id)? {
+        if latest.created_at + AUTO_VERSION_INTERVAL_SECONDS > get_now_as_secs()
+            || latest.body == page_content.body
+        {
+            return Ok(None);
+        }
+    }
+
+    let version = create_page_content_version(conn, page_content, None, true, creator_id)?;
+    Ok(Some(version))
+}
+
+// Restore body and quizzes of the version. The current content is kept as a new version,
+// so restoring can be undone. Submissions are re-graded if the answer key of an assignment
+// quiz is changed by restoring.
+pub fn restore_page_content_version(
+    conn: &mut PgConnection,
+    version: &PageContentVersion,
+    user_id: i32,
+) -> Result<PageContent, IkigaiError> {
+    let (page_content, regrades) = conn.transaction::<_, IkigaiError, _>(|conn| {
+        let mut page_content = PageContent::find(conn, version.page_content_id)?;
+        create_page_content_version(
+            conn,
+            &page_content,
+            Some("Before restoring".into()),
+            true,
+            user_id,
+        )?;
+
+        let mut content: JSONContent =
+            serde_json::from_value(version.body.clone()).unwrap_or_default();
+        let quiz_ids = version
+            .quiz_data
+            .quizzes
+            .iter()
+            .map(|quiz| quiz.id)
+            .collect();
+        let existing_quizzes = Quiz::find_all(conn, &quiz_ids)?;
+        let mut answer_changed_quiz_ids = vec![];
+        for version_quiz in version.quiz_data.quizzes.iter() {
+            let existing_quiz = existing_quizzes
+                .iter()
+                .find(|quiz| quiz.id == version_quiz.id);
+            let mut quiz = match existing_quiz {
+                Some(quiz) => quiz.clone(),
+                None => QuizBuilder::default()
+                    .id(version_quiz.id)
+                    .page_content_id(page_content.id)
+                    .creator_id(user_id)
+                    .quiz_type(version_quiz.quiz_type)
+                    .question_data(version_quiz.question_data.clone())
+                    .answer_data(version_quiz.answer_data.clone())
+                    .build()
+                    .map_err(|_| IkigaiError::InternalServerError)?,
+            };
+
+            // The quiz has been moved to other page content, restore a copy of it
+            if quiz.page_content_id != page_content.id {
+                quiz.id = Uuid::new_v4();
+                quiz.page_content_id = page_content.id;
+                content.replace_block_id(
+                    version_quiz.quiz_type.block_name(),
+                    version_quiz.quiz_type.id_name(),
+                    &serde_json::to_value(version_quiz.id).unwrap_or_default(),
+                    &serde_json::to_value(quiz.id).unwrap_or_default(),
+                );
+            } else if existing_quiz.is_some()
+                && (quiz.answer_data != version_quiz.answer_data
+                    || quiz.points != version_quiz.points)
+            {
+                answer_changed_quiz_ids.push(quiz.id);
+            }
+
+            quiz.quiz_type = version_quiz.quiz_type;
+            quiz.question_data = version_quiz.question_data.clone();
+            quiz.answer_data = version_quiz.answer_data.clone();
+            quiz.points = version_quiz.points;
+            Quiz::upsert(conn, quiz)?;
+        }
+
+        page_content.body = serde_json::to_value(content)?;
+        let page_content = PageContent::upsert(conn, page_content)?;
+
+        let page = Page::find(conn, page_content.page_id)?;
+        refresh_assignment_max_score(conn, page.document_id)?;
+
+        let mut regrades = vec![];
+        if let Some(assignment) = Assignment::find_by_document(conn, page.document_id)? {
+            let graded_quiz_ids =
+                Quiz::find_all_by_original_quizzes(conn, &answer_changed_quiz_ids)?
+                    .into_iter()
+                    .filter_map(|(quiz, _)| quiz.original_quiz_id)
+                    .unique()
+                    .collect::<Vec<Uuid>>();
+            for quiz_id in graded_quiz_ids {
+                let regrade = AssignmentRegrade::new(assignment.id, quiz_id, user_id);
+                regrades.push(AssignmentRegrade::insert(conn, regrade)?);
+            }
+        }
+
+        Ok((page_content, regrades))
+    })?;
+
+    // Jobs are added after committing, so they see the restored answer key
+    for regrade in regrades.iter() {
+        add_regrade_quiz_job(regrade)?;
+    }
+
+    Ok(page_content)
+}
+
+// Diff top level blocks of two tiptap contents by their longest common subsequence.
+// Within a run of changes, removed and added blocks of the same type are paired in order
+// as modified blocks.
+pub fn diff_page_content_body(old_body: &Value, new_body: &Value) -> Vec<PageContentBlockDiff> {
+    let old_blocks = get_top_level_blocks(old_body);
+    let new_blocks = get_top_level_blocks(new_body);
+
+    let (old_len, new_len) = (old_blocks.len(), new_blocks.len());
+    let mut lcs = vec![vec![0; new_len + 1]; old_len + 1];
+    for i in (0..old_len).rev() {
+        for j in (0..new_len).rev() {
+            lcs[i][j] = if old_blocks[i] == new_blocks[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diffs = vec![];
+    let (mut removed, mut added) = (vec![], vec![]);
+    let (mut i, mut j) = (0, 0);
+    while i < old_len || j < new_len {
+        if i < old_len && j < new_len && old_blocks[i] == new_blocks[j] {
+            push_changed_blocks(&mut diffs, &old_blocks, &new_blocks, &removed, &added);
+            removed.clear();
+            added.clear();
+            diffs.push(PageContentBlockDiff {
+                change_type: BlockChangeType::Unchanged,
+                old_index: Some(i as i32),
+                new_index: Some(j as i32),
+                old_block: Some(old_blocks[i].clone()),
+                new_block: Some(new_blocks[j].clone()),
+            });
+            i += 1;
+            j += 1;
+        } else if i < old_len && (j == new_len || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            removed.push(i);
+            i += 1;
+        } else {
+            added.push(j);
+            j += 1;
+        }
+    }
+    push_changed_blocks(&mut diffs, &old_blocks, &new_blocks, &removed, &added);
+
+    diffs
+}
+
+// Pair each added block with the next unpaired removed block of the same type,
+// keeping both sides in order.
+fn push_changed_blocks(
+    diffs: &mut Vec<PageContentBlockDiff>,
+    old_blocks: &[Value],
+    new_blocks: &[Value],
+    removed: &[usize],
+    added: &[usize],
+) {
+    let removed_diff = |i: usize| PageContentBlockDiff {
+        change_type: BlockChangeType::Removed,
+        old_index: Some(i as i32),
+        new_index: None,
+        old_block: Some(old_blocks[i].clone()),
+        new_block: None,
+    };
+    let added_diff = |j: usize| PageContentBlockDiff {
+        change_type: BlockChangeType::Added,
+        old_index: None,
+        new_index: Some(j as i32),
+        old_block: None,
+        new_block: Some(new_blocks[j].clone()),
+    };
+
+    let (mut removed_pos, mut added_pos) = (0, 0);
+    for (pos, &j) in added.iter().enumerate() {
+        let paired = removed[removed_pos..]
+            .iter()
+            .position(|&i| old_blocks[i].get("type") == new_blocks[j].get("type"));
+        let Some(offset) = paired else {
+            continue;
+        };
+
+        let i = removed[removed_pos + offset];
+        diffs.extend(
+            removed[removed_pos..removed_pos + offset]
+                .iter()
+                .map(|&i| removed_diff(i)),
+        );
+        diffs.extend(added[added_pos..pos].iter().map(|&j| added_diff(j)));
+        diffs.push(PageContentBlockDiff {
+            change_type: BlockChangeType::Modified,
+            old_index: Some(i as i32),
+            new_index: Some(j as i32),
+            old_block: Some(old_blocks[i].clone()),
+            new_block: Some(new_blocks[j].clone()),
+        });
+        removed_pos += offset + 1;
+        added_pos = pos + 1;
+    }
+    diffs.extend(removed[removed_pos..].iter().map(|&i| removed_diff(i)));
+    diffs.extend(added[added_pos..].iter().map(|&j| added_diff(j)));
+}
+
+fn get_top_level_blocks(body: &Value) -> Vec<Value> {
+    body.get("content")
+        .and_then(|content| content.as_array())
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+    use serde_json::json;
+
+    use super::*;
+
+    fn paragraph(text: &str) -> Value {
+        json!({ "type": "paragraph", "content": [{ "type": "text", "text": text }] })
+    }
+
+    fn heading(text: &str) -> Value {
+        json!({ "type": "heading", "content": [{ "type": "text", "text": text }] })
+    }
+
+    fn doc(blocks: Vec<Value>) -> Value {
+        json!({ "type": "doc", "content": blocks })
+    }
+
+    fn summarize(
+        diffs: &[PageContentBlockDiff],
+    ) -> Vec<(BlockChangeType, Option<i32>, Option<i32>)> {
+        diffs
+            .iter()
+            .map(|diff| (diff.change_type, diff.old_index, diff.new_index))
+            .collect()
+    }
+
+    #[test]
+    fn diff_unchanged_added_and_removed() {
+        let old_body = doc(vec![paragraph("a"), paragraph("b"), paragraph("c")]);
+        let new_body = doc(vec![paragraph("a"), paragraph("c"), heading("d")]);
+        let diffs = diff_page_content_body(&old_body, &new_body);
+
+        assert_eq!(
+            summarize(&diffs),
+            vec![
+                (BlockChangeType::Unchanged, Some(0), Some(0)),
+                (BlockChangeType::Removed, Some(1), None),
+                (BlockChangeType::Unchanged, Some(2), Some(1)),
+                (BlockChangeType::Added, None, Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_adjacent_edits_are_modified() {
+        let old_body = doc(vec![paragraph("a"), paragraph("b"), paragraph("c")]);
+        let new_body = doc(vec![paragraph("a"), paragraph("b2"), paragraph("c2")]);
+        let diffs = diff_page_content_body(&old_body, &new_body);
+
+        assert_eq!(
+            summarize(&diffs),
+            vec![
+                (BlockChangeType::Unchanged, Some(0), Some(0)),
+                (BlockChangeType::Modified, Some(1), Some(1)),
+                (BlockChangeType::Modified, Some(2), Some(2)),
+            ]
+        );
+        assert_eq!(diffs[1].old_block, Some(paragraph("b")));
+        assert_eq!(diffs[1].new_block, Some(paragraph("b2")));
+    }
+
+    #[test]
+    fn diff_pairs_blocks_by_type() {
+        let old_body = doc(vec![heading("a"), paragraph("b")]);
+        let new_body = doc(vec![paragraph("b2"), heading("e"), paragraph("f")]);
+        let diffs = diff_page_content_body(&old_body, &new_body);
+
+        assert_eq!(
+            summarize(&diffs),
+            vec![
+                (BlockChangeType::Removed, Some(0), None),
+                (BlockChangeType::Modified, Some(1), Some(0)),
+                (BlockChangeType::Added, None, Some(1)),
+                (BlockChangeType::Added, None, Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_empty_bodies() {
+        assert!(diff_page_content_body(&json!({}), &json!({})).is_empty());
+
+        let new_body = doc(vec![paragraph("a")]);
+        let diffs = diff_page_content_body(&json!({}), &new_body);
+        assert_eq!(
+            summarize(&diffs),
+            vec![(BlockChangeType::Added, None, Some(0))]
+        );
+    }
+}
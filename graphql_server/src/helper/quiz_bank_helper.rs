@@ -0,0 +1,64 @@
+use diesel::{Connection, PgConnection};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::db::*;
+use crate::error::IkigaiError;
+use crate::helper::refresh_assignment_max_score;
+
+// Push content of the bank quiz to its copies.
+// Copies in submitted submissions are kept as they are, so students' work stays untouched.
+pub fn propagate_bank_quiz(
+    conn: &mut PgConnection,
+    bank_quiz: &BankQuiz,
+) -> Result<Vec<Quiz>, IkigaiError> {
+    let copies = Quiz::find_all_by_bank_quiz(conn, bank_quiz.id)?;
+    let document_ids = copies
+        .iter()
+        .map(|(_, document_id)| *document_id)
+        .unique()
+        .collect();
+    let submitted_document_ids: HashSet<Uuid> = Submission::find_by_documents(conn, &document_ids)?
+        .into_iter()
+        .filter(|submission| submission.submit_at.is_some())
+        .map(|submission| submission.document_id)
+        .collect();
+    let (quiz_ids, document_ids): (Vec<Uuid>, Vec<Uuid>) = copies
+        .into_iter()
+        .filter(|(_, document_id)| !submitted_document_ids.contains(document_id))
+        .map(|(quiz, document_id)| (quiz.id, document_id))
+        .unzip();
+    if quiz_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    conn.transaction(|conn| {
+        let quizzes = Quiz::update_content(
+            conn,
+            &quiz_ids,
+            &bank_quiz.question_data,
+            &bank_quiz.answer_data,
+            bank_quiz.points,
+        )?;
+
+        // Answers of in-progress copies are re-scored against the new expected answer
+        let quiz_by_id: HashMap<Uuid, &Quiz> = quizzes.iter().map(|quiz| (quiz.id, quiz)).collect();
+        for answer in QuizUserAnswer::find_all_by_quizzes(conn, &quiz_ids)? {
+            if let Some(quiz) = quiz_by_id.get(&answer.quiz_id) {
+                let score = try_get_auto_score(
+                    quiz.quiz_type,
+                    quiz.answer_data.clone(),
+                    answer.answer_data,
+                );
+                QuizUserAnswer::update_score(conn, answer.quiz_id, answer.user_id, score)?;
+            }
+        }
+
+        for document_id in document_ids.into_iter().unique() {
+            refresh_assignment_max_score(conn, document_id)?;
+        }
+
+        Ok(quizzes)
+    })
+}
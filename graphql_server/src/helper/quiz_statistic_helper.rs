@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use diesel::PgConnection;
+use itertools::Itertools;
+use uuid::Uuid;
+
+use crate::db::*;
+use crate::error::IkigaiError;
+use crate::helper::find_document_quizzes;
+
+// Share of top and bottom students compared by the discrimination index
+const DISCRIMINATION_GROUP_RATIO: f64 = 0.27;
+const MAX_COMMON_WRONG_ANSWERS: usize = 5;
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct QuizStatistic {
+    // Quiz of the assignment document
+    pub quiz_id: Uuid,
+    pub quiz_type: QuizType,
+    pub points: f64,
+    // Submissions which got this quiz
+    pub number_of_submissions: i32,
+    pub number_of_answers: i32,
+    pub percent_correct: f64,
+    // In points of the quiz, unanswered counts as 0
+    pub average_score: f64,
+    // From -1 to 1, low or negative values point to a bad question
+    pub discrimination_index: Option<f64>,
+    pub choice_distribution: Vec<QuizChoiceStatistic>,
+    pub common_wrong_answers: Vec<QuizAnswerStatistic>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct QuizChoiceStatistic {
+    pub choice_id: Uuid,
+    pub is_expected: bool,
+    pub count: i32,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct QuizAnswerStatistic {
+    pub answer: String,
+    pub count: i32,
+}
+
+// Item analysis of submitted submissions. Quizzes of submissions are linked back to the quizzes
+// of the assignment by `original_quiz_id`.
+pub fn get_assignment_quiz_statistics(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+) -> Result<Vec<QuizStatistic>, IkigaiError> {
+    let quizzes = find_document_quizzes(conn, assignment.document_id)?;
+    let submissions: HashMap<Uuid, Submission> =
+        Submission::find_all_by_assignment(conn, assignment.id)?
+            .into_iter()
+            .filter(|submission| submission.submit_at.is_some())
+            .map(|submission| (submission.document_id, submission))
+            .collect();
+
+    let quiz_ids = quizzes.iter().map(|quiz| quiz.id).collect();
+    let cloned_quizzes: Vec<(Quiz, &Submission)> =
+        Quiz::find_all_by_original_quizzes(conn, &quiz_ids)?
+            .into_iter()
+            .filter_map(|(quiz, document_id)| Some((quiz, submissions.get(&document_id)?)))
+            .collect();
+    let cloned_quiz_ids = cloned_quizzes.iter().map(|(quiz, _)| quiz.id).collect();
+    let answers: HashMap<Uuid, QuizUserAnswer> =
+        QuizUserAnswer::find_all_by_quizzes(conn, &cloned_quiz_ids)?
+            .into_iter()
+            .map(|answer| (answer.quiz_id, answer))
+            .collect();
+
+    let mut statistics = vec![];
+    for quiz in quizzes {
+        // Score from 0 to 1 and answer of each submission which got this quiz
+        let results: Vec<(&Submission, f64, Option<&QuizUserAnswer>)> = cloned_quizzes
+            .iter()
+            .filter(|(cloned_quiz, _)| cloned_quiz.original_quiz_id == Some(quiz.id))
+            .map(|(cloned_quiz, submission)| {
+                let answer = answers
+                    .get(&cloned_quiz.id)
+                    .filter(|answer| answer.user_id == submission.user_id);
+                let score = answer.map_or(0.0, |answer| answer.final_score(quiz.points));
+                (*submission, score, answer)
+            })
+            .collect();
+        let answered: Vec<&QuizUserAnswer> = results
+            .iter()
+            .filter_map(|(_, _, answer)| *answer)
+            .collect();
+
+        let number_of_submissions = results.len();
+        let (percent_correct, average_score) = if number_of_submissions > 0 {
+            let correct = results.iter().filter(|(_, score, _)| *score >= 1.0).count();
+            let total: f64 = results.iter().map(|(_, score, _)| score).sum();
+            (
+                correct as f64 * 100.0 / number_of_submissions as f64,
+                total * quiz.points / number_of_submissions as f64,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+
+        statistics.push(QuizStatistic {
+            quiz_id: quiz.id,
+            quiz_type: quiz.quiz_type,
+            points: quiz.points,
+            number_of_submissions: number_of_submissions as i32,
+            number_of_answers: answered.len() as i32,
+            percent_correct,
+            average_score,
+            discrimination_index: discrimination_index(&results),
+            choice_distribution: choice_distribution(&quiz, &answered),
+            common_wrong_answers: common_wrong_answers(&quiz, &answered),
+        });
+    }
+
+    Ok(statistics)
+}
+
+// Average quiz score of the top group minus the one of the bottom group,
+// students are ranked by their final grade.
+fn discrimination_index(results: &[(&Submission, f64, Option<&QuizUserAnswer>)]) -> Option<f64> {
+    let ranked = results
+        .iter()
+        .sorted_by(|(a, _, _), (b, _, _)| {
+            let a = a.final_grade.or(a.auto_grade).unwrap_or_default();
+            let b = b.final_grade.or(b.auto_grade).unwrap_or_default();
+            b.total_cmp(&a)
+        })
+        .map(|(_, score, _)| *score)
+        .collect_vec();
+    if ranked.len() < 2 {
+        return None;
+    }
+
+    let group_size = ((ranked.len() as f64 * DISCRIMINATION_GROUP_RATIO).round() as usize)
+        .clamp(1, ranked.len() / 2);
+    let upper: f64 = ranked.iter().take(group_size).sum();
+    let lower: f64 = ranked.iter().rev().take(group_size).sum();
+    Some((upper - lower) / group_size as f64)
+}
+
+fn choice_distribution(quiz: &Quiz, answers: &[&QuizUserAnswer]) -> Vec<QuizChoiceStatistic> {
+    let (options, expected_choices): (Vec<ChoiceOption>, Vec<Uuid>) = match quiz.quiz_type {
+        QuizType::SingleChoice | QuizType::MultipleChoice => {
+            let question: Option<ChoiceQuestionData> =
+                serde_json::from_value(quiz.question_data.clone()).ok();
+            let answer: Option<ChoiceAnswerData> =
+                serde_json::from_value(quiz.answer_data.clone()).ok();
+            (
+                question
+                    .map(|question| question.options)
+                    .unwrap_or_default(),
+                answer
+                    .map(|answer| answer.expected_choices)
+                    .unwrap_or_default(),
+            )
+        }
+        QuizType::SelectOption => {
+            let question: Option<SelectQuestionData> =
+                serde_json::from_value(quiz.question_data.clone()).ok();
+            let answer: Option<SelectAnswerData> =
+                serde_json::from_value(quiz.answer_data.clone()).ok();
+            (
+                question
+                    .map(|question| question.options)
+                    .unwrap_or_default(),
+                answer
+                    .map(|answer| answer.expected_choices)
+                    .unwrap_or_default(),
+            )
+        }
+        _ => return vec![],
+    };
+
+    let mut counts: HashMap<Uuid, i32> = HashMap::new();
+    for answer in answers {
+        let choices = if quiz.quiz_type == QuizType::SelectOption {
+            answer
+                .parse_answer_data::<SelectUserAnswerData>()
+                .map(|answer| vec![answer.choice])
+        } else {
+            answer
+                .parse_answer_data::<ChoiceUserAnswerData>()
+                .map(|answer| answer.choices)
+        };
+        for choice in choices.unwrap_or_default() {
+            *counts.entry(choice).or_default() += 1;
+        }
+    }
+
+    options
+        .into_iter()
+        .map(|option| QuizChoiceStatistic {
+            choice_id: option.id,
+            is_expected: expected_choices.contains(&option.id),
+            count: counts.get(&option.id).copied().unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn common_wrong_answers(quiz: &Quiz, answers: &[&QuizUserAnswer]) -> Vec<QuizAnswerStatistic> {
+    if quiz.quiz_type != QuizType::FillInBlank {
+        return vec![];
+    }
+
+    let mut counts: HashMap<String, i32> = HashMap::new();
+    for answer in answers.iter().filter(|answer| answer.score < 1.0) {
+        if let Some(user_answer) = answer.parse_answer_data::<FillInBlankUserAnswerData>() {
+            let normalized = user_answer.answer.trim().to_lowercase();
+            if !normalized.is_empty() {
+                *counts.entry(normalized).or_default() += 1;
+            }
+        }
+    }
+
+    counts
+        .into_iter()
+        .sorted_by(|(a_answer, a_count), (b_answer, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_answer.cmp(b_answer))
+        })
+        .take(MAX_COMMON_WRONG_ANSWERS)
+        .map(|(answer, count)| QuizAnswerStatistic { answer, count })
+        .collect()
+}
@@ -0,0 +1,194 @@
+use diesel::PgConnection;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::db::*;
+use crate::error::IkigaiError;
+use crate::helper::find_document_quizzes;
+use crate::service::ikigai_ai::GradeByRubricRequestData;
+
+// Request grading a writing block of the submission by the rubric of the submission.
+// Students write their answer into the writing block copied into the submission document,
+// so the copy holds the prompt of the teacher followed by the answer.
+pub fn get_rubric_grading_request(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+    submission: &Submission,
+    quiz_id: Uuid,
+    rubric: RubricTableData,
+) -> Result<GradeByRubricRequestData, IkigaiError> {
+    let quiz = find_document_quizzes(conn, submission.document_id)?
+        .into_iter()
+        .find(|quiz| quiz.id == quiz_id)
+        .ok_or_else(|| IkigaiError::new_bad_request("Quiz is not in the submission"))?;
+    if quiz.quiz_type != QuizType::WritingBlock {
+        return Err(IkigaiError::new_bad_request(
+            "Only writing block can be graded by rubric",
+        ));
+    }
+
+    let writing_text = get_writing_text(&quiz);
+    let prompt = find_writing_prompt(conn, assignment, &quiz, &writing_text)?;
+    let (mut answer, _) = strip_prompt(&writing_text, &prompt);
+    if let Some(user_answer) = QuizUserAnswer::find_opt(conn, quiz.id, submission.user_id)? {
+        answer.extend(get_text_nodes(&user_answer.answer_data));
+    }
+    let answer = answer.join(" ");
+    if answer.trim().is_empty() {
+        return Err(IkigaiError::new_bad_request(
+            "Student has not written anything in this block",
+        ));
+    }
+
+    Ok(GradeByRubricRequestData {
+        question: prompt.join(" "),
+        answer: answer.trim().to_string(),
+        rubric,
+    })
+}
+
+// Text of the writing block of the assignment which the quiz is copied from. Copies edited
+// before their lineage was kept are matched by the writing block whose text they start with.
+fn find_writing_prompt(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+    quiz: &Quiz,
+    writing_text: &[String],
+) -> Result<Vec<String>, IkigaiError> {
+    if let Some(original_quiz_id) = quiz.original_quiz_id {
+        if let Ok(original_quiz) = Quiz::find(conn, original_quiz_id) {
+            return Ok(get_writing_text(&original_quiz));
+        }
+    }
+
+    let prompt = find_document_quizzes(conn, assignment.document_id)?
+        .iter()
+        .filter(|assignment_quiz| assignment_quiz.quiz_type == QuizType::WritingBlock)
+        .map(get_writing_text)
+        .map(|prompt| {
+            let (_, total_prompt_nodes) = strip_prompt(writing_text, &prompt);
+            (prompt, total_prompt_nodes)
+        })
+        .filter(|(_, total_prompt_nodes)| *total_prompt_nodes > 0)
+        .max_by_key(|(_, total_prompt_nodes)| *total_prompt_nodes)
+        .map(|(prompt, _)| prompt)
+        .unwrap_or_default();
+    Ok(prompt)
+}
+
+fn get_writing_text(quiz: &Quiz) -> Vec<String> {
+    serde_json::from_value::<WritingQuestionData>(quiz.question_data.clone())
+        .map(|question| get_text_nodes(&question.content))
+        .unwrap_or_default()
+}
+
+// Text nodes of the prompt are removed in order, along with the number of removed ones.
+// A node the student has typed after (e.g. in the same paragraph) keeps only what comes
+// after the prompt text.
+fn strip_prompt(text: &[String], prompt: &[String]) -> (Vec<String>, usize) {
+    let mut prompt_nodes = prompt.iter().peekable();
+    let mut answer = vec![];
+    for node in text {
+        match prompt_nodes.peek() {
+            Some(prompt_node) if node == *prompt_node => {
+                prompt_nodes.next();
+            }
+            Some(prompt_node) if node.starts_with(prompt_node.as_str()) => {
+                answer.push(node[prompt_node.len()..].trim().to_string());
+                prompt_nodes.next();
+            }
+            _ => answer.push(node.clone()),
+        }
+    }
+
+    answer.retain(|node| !node.trim().is_empty());
+    (answer, prompt.len() - prompt_nodes.len())
+}
+
+// Plain text nodes of editor content
+fn get_text_nodes(content: &Value) -> Vec<String> {
+    let mut nodes = vec![];
+    extract_text(content, &mut nodes);
+    nodes
+}
+
+fn extract_text(content: &Value, nodes: &mut Vec<String>) {
+    match content {
+        Value::Object(node) => {
+            if let Some(Value::String(node_text)) = node.get("text") {
+                nodes.push(node_text.clone());
+            }
+            if let Some(children) = node.get("content") {
+                extract_text(children, nodes);
+            }
+        }
+        Value::Array(children) => children.iter().for_each(|node| extract_text(node, nodes)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+
+    use super::*;
+
+    fn nodes(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    #[test]
+    fn strip_prompt_keeps_the_answer() {
+        let prompt = nodes(&["Describe your city.", "Use 150 words."]);
+        let text = nodes(&["Describe your city.", "Use 150 words.", "My city is Hue."]);
+        assert_eq!(
+            strip_prompt(&text, &prompt),
+            (nodes(&["My city is Hue."]), 2)
+        );
+
+        let typed_in_prompt = nodes(&["Describe your city. It is small.", "Use 150 words."]);
+        assert_eq!(
+            strip_prompt(&typed_in_prompt, &prompt),
+            (nodes(&["It is small."]), 2)
+        );
+
+        let answer_first = nodes(&["Hue.", "Describe your city.", "Use 150 words."]);
+        assert_eq!(strip_prompt(&answer_first, &prompt), (nodes(&["Hue."]), 2));
+    }
+
+    #[test]
+    fn strip_prompt_without_answer() {
+        let prompt = nodes(&["Describe your city."]);
+        assert_eq!(strip_prompt(&prompt, &prompt), (vec![], 1));
+        assert_eq!(
+            strip_prompt(&nodes(&["Describe your city.", "  "]), &prompt),
+            (vec![], 1)
+        );
+        assert_eq!(strip_prompt(&nodes(&["Hue"]), &[]), (nodes(&["Hue"]), 0));
+        assert_eq!(
+            strip_prompt(&nodes(&["Hue"]), &prompt),
+            (nodes(&["Hue"]), 0)
+        );
+    }
+
+    #[test]
+    fn get_text_nodes_of_editor_content() {
+        let content = serde_json::json!({
+            "type": "doc",
+            "content": [
+                { "type": "paragraph", "content": [{ "type": "text", "text": "Hello" }] },
+                { "type": "paragraph" },
+                {
+                    "type": "bulletList",
+                    "content": [{
+                        "type": "listItem",
+                        "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "world" }] }]
+                    }]
+                }
+            ]
+        });
+        assert_eq!(get_text_nodes(&content), nodes(&["Hello", "world"]));
+        assert!(get_text_nodes(&Value::Null).is_empty());
+    }
+}
@@ -1,12 +1,15 @@
-use crate::background_job::submission_job::CompleteSubmission;
+use crate::background_job::submission_job::{CloseAssignment, CompleteSubmission, RegradeQuiz};
 use actix::SystemService;
 use aj::{JobBuilder, JobType, AJ};
 use diesel::{Connection, PgConnection};
+use itertools::Itertools;
 use uuid::Uuid;
 
 use crate::db::*;
 use crate::error::IkigaiError;
-use crate::graphql::notification_center::{NotificationCenter, SubmitCompleted};
+use crate::graphql::notification_center::{
+    NotificationCenter, SubmissionDeadlineChanged, SubmitCompleted,
+};
 use crate::helper::DocumentCloneConfigBuilder;
 use crate::notification_center::send_notification;
 use crate::util::{get_date_from_ts, get_now_as_secs};
@@ -17,17 +20,21 @@ pub fn submit_submission(
     assignment: &Assignment,
     notify_student: bool,
 ) -> Result<(), IkigaiError> {
-    let grade = auto_grade(conn, submission.document_id, submission.user_id)?;
-
-    let mut final_grade = grade;
-    if let Some(band_score_id) = assignment.band_score_id {
-        let band_score = BandScore::find(conn, band_score_id)?;
-        final_grade = band_score.find_score(grade);
-    }
+    let is_late = assignment.is_late(get_now_as_secs());
+    let (grade, final_grade) = compute_submission_grade(conn, submission, assignment, is_late)?;
 
     // Auto release grade in case teacher choose auto grade
     let is_auto_grade = assignment.grade_method == GradeMethod::Auto;
-    Submission::submit(conn, submission.id, grade, final_grade, is_auto_grade)?;
+    Submission::submit(
+        conn,
+        submission.id,
+        grade,
+        final_grade,
+        is_auto_grade,
+        is_late,
+    )?;
+    let needs_grading = submission_needs_grading(conn, submission)?;
+    Submission::update_needs_grading(conn, submission.id, needs_grading)?;
     if notify_student {
         NotificationCenter::from_registry().do_send(SubmitCompleted {
             user_id: submission.user_id,
@@ -38,6 +45,349 @@ pub fn submit_submission(
     Ok(())
 }
 
+// Auto grade and final grade after band conversion and late penalty
+pub fn compute_submission_grade(
+    conn: &mut PgConnection,
+    submission: &Submission,
+    assignment: &Assignment,
+    is_late: bool,
+) -> Result<(f64, f64), IkigaiError> {
+    let grade = auto_grade(
+        conn,
+        submission.document_id,
+        submission.user_id,
+        assignment.negative_marking,
+    )?;
+
+    let mut final_grade = grade;
+    if let Some(band_score_id) = assignment.band_score_id {
+        let band_score = BandScore::find(conn, band_score_id)?;
+        let max_score = get_submission_max_score(conn, assignment, submission)?;
+        final_grade = band_score.find_score(grade, max_score);
+    }
+
+    if let (true, Some(late_penalty)) = (is_late, assignment.late_penalty) {
+        final_grade -= final_grade * late_penalty;
+    }
+
+    Ok((grade, final_grade))
+}
+
+pub fn schedule_complete_submission(submission: &Submission) -> Result<(), IkigaiError> {
+    if let Some(deadline_at) = submission.deadline_at {
+        let message = CompleteSubmission {
+            attempt_number: submission.attempt_number,
+            submission_id: submission.id,
+            deadline_at: Some(deadline_at),
+        };
+        let job = JobBuilder::default()
+            .message(message)
+            .job_type(JobType::ScheduledAt(get_date_from_ts(deadline_at)))
+            .build()?;
+        AJ::add_job(job);
+    }
+
+    Ok(())
+}
+
+// Move deadline of a running test, extra seconds can be negative to shorten the test
+pub fn extend_submission_time(
+    conn: &mut PgConnection,
+    submission: &Submission,
+    extra_seconds: i64,
+) -> Result<Submission, IkigaiError> {
+    let deadline_at = submission
+        .deadline_at
+        .map(|deadline_at| deadline_at + extra_seconds);
+    let submission =
+        Submission::update_deadline(conn, submission.id, deadline_at, submission.paused_at)?;
+    if submission.paused_at.is_none() {
+        schedule_complete_submission(&submission)?;
+    }
+    notify_deadline_changed(&submission);
+
+    Ok(submission)
+}
+
+pub fn pause_submission(
+    conn: &mut PgConnection,
+    submission: &Submission,
+) -> Result<Submission, IkigaiError> {
+    if submission.paused_at.is_some() {
+        return Ok(submission.clone());
+    }
+
+    let submission = Submission::update_deadline(
+        conn,
+        submission.id,
+        submission.deadline_at,
+        Some(get_now_as_secs()),
+    )?;
+    notify_deadline_changed(&submission);
+
+    Ok(submission)
+}
+
+// Paused time is given back to the student
+pub fn resume_submission(
+    conn: &mut PgConnection,
+    submission: &Submission,
+) -> Result<Submission, IkigaiError> {
+    let Some(paused_at) = submission.paused_at else {
+        return Ok(submission.clone());
+    };
+
+    let paused_seconds = get_now_as_secs() - paused_at;
+    let deadline_at = submission
+        .deadline_at
+        .map(|deadline_at| deadline_at + paused_seconds);
+    let submission = Submission::update_deadline(conn, submission.id, deadline_at, None)?;
+    schedule_complete_submission(&submission)?;
+    notify_deadline_changed(&submission);
+
+    Ok(submission)
+}
+
+fn notify_deadline_changed(submission: &Submission) {
+    let deadline_at = if submission.paused_at.is_some() {
+        None
+    } else {
+        submission.deadline_at
+    };
+    NotificationCenter::from_registry().do_send(SubmissionDeadlineChanged {
+        user_id: submission.user_id,
+        submission_id: submission.id,
+        deadline_at,
+    });
+}
+
+// Only grades released to the student are counted in case of `released_only`
+pub fn get_assignment_grade(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+    user_id: i32,
+    released_only: bool,
+) -> Result<AssignmentGrade, IkigaiError> {
+    let submissions = Submission::find_all_by_assignment_and_user(conn, user_id, assignment.id)?;
+    Ok(resolve_assignment_grade(
+        assignment,
+        user_id,
+        &submissions,
+        released_only,
+    ))
+}
+
+pub fn resolve_assignment_grade(
+    assignment: &Assignment,
+    user_id: i32,
+    submissions: &[Submission],
+    released_only: bool,
+) -> AssignmentGrade {
+    let submissions: Vec<Submission> = submissions
+        .iter()
+        .filter(|submission| submission.submit_at.is_some())
+        .filter(|submission| !released_only || submission.allow_for_student_view_answer)
+        .cloned()
+        .collect();
+    let resolved_grade = assignment.grade_policy.resolve(&submissions);
+    let number_of_graded_attempts = submissions
+        .iter()
+        .filter(|submission| submission.final_grade.is_some())
+        .count();
+
+    AssignmentGrade {
+        assignment_id: assignment.id,
+        user_id,
+        grade_policy: assignment.grade_policy,
+        grade: resolved_grade.map(|(grade, _)| grade),
+        submission_id: resolved_grade.and_then(|(_, submission_id)| submission_id),
+        number_of_graded_attempts: number_of_graded_attempts as i32,
+    }
+}
+
+// Push the answer key of an assignment quiz to its copies in submissions, then re-score answers
+// and re-grade submitted submissions. Final grades given by teacher are kept.
+pub fn regrade_quiz(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+    quiz: &Quiz,
+) -> Result<RegradeSummary, IkigaiError> {
+    let copies = Quiz::find_all_by_original_quizzes(conn, &vec![quiz.id])?;
+    let document_ids = copies
+        .iter()
+        .map(|(_, document_id)| *document_id)
+        .unique()
+        .collect();
+    let submissions: Vec<Submission> = Submission::find_by_documents(conn, &document_ids)?
+        .into_iter()
+        .filter(|submission| submission.assignment_id == assignment.id)
+        .collect();
+    let quiz_ids: Vec<Uuid> = copies
+        .into_iter()
+        .filter(|(_, document_id)| {
+            submissions
+                .iter()
+                .any(|submission| submission.document_id == *document_id)
+        })
+        .map(|(quiz, _)| quiz.id)
+        .collect();
+    if quiz_ids.is_empty() {
+        return Ok(RegradeSummary::default());
+    }
+
+    conn.transaction(|conn| {
+        let quizzes = Quiz::update_answer_key(conn, &quiz_ids, &quiz.answer_data, quiz.points)?;
+        let answers = QuizUserAnswer::find_all_by_quizzes(conn, &quiz_ids)?;
+        for answer in answers.iter() {
+            let score = try_get_auto_score(
+                quiz.quiz_type,
+                quiz.answer_data.clone(),
+                answer.answer_data.clone(),
+            );
+            QuizUserAnswer::update_score(conn, answer.quiz_id, answer.user_id, score)?;
+        }
+
+        let mut changes = vec![];
+        for submission in submissions.iter() {
+            if let Some(change) = regrade_submission(conn, assignment, submission)? {
+                changes.push(change);
+            }
+        }
+
+        Ok(RegradeSummary {
+            number_of_quizzes: quizzes.len() as i32,
+            number_of_answers: answers.len() as i32,
+            changes,
+        })
+    })
+}
+
+// Compute grades of a submitted submission again, e.g. after its answers are re-scored.
+// Final grade given by teacher is kept. None if nothing is changed.
+pub fn regrade_submission(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+    submission: &Submission,
+) -> Result<Option<RegradeGradeChange>, IkigaiError> {
+    if submission.submit_at.is_none() {
+        return Ok(None);
+    }
+
+    let student_assignment = get_student_assignment(conn, assignment.clone(), submission.user_id)?;
+    let (grade, final_grade) =
+        compute_submission_grade(conn, submission, &student_assignment, submission.is_late)?;
+    let is_final_grade_kept =
+        assignment.grade_method != GradeMethod::Auto && submission.feedback_at.is_some();
+    let new_final_grade = if is_final_grade_kept {
+        submission.final_grade
+    } else {
+        Some(final_grade)
+    };
+    if submission.auto_grade == Some(grade) && submission.final_grade == new_final_grade {
+        return Ok(None);
+    }
+
+    Submission::update_grades(conn, submission.id, grade, new_final_grade)?;
+    Ok(Some(RegradeGradeChange {
+        submission_id: submission.id,
+        user_id: submission.user_id,
+        old_auto_grade: submission.auto_grade,
+        new_auto_grade: grade,
+        old_final_grade: submission.final_grade,
+        new_final_grade,
+        is_final_grade_kept,
+    }))
+}
+
+// Answered writing blocks must be scored by teacher
+pub fn submission_needs_grading(
+    conn: &mut PgConnection,
+    submission: &Submission,
+) -> Result<bool, IkigaiError> {
+    let writing_quiz_ids = find_document_quizzes(conn, submission.document_id)?
+        .into_iter()
+        .filter(|quiz| quiz.quiz_type == QuizType::WritingBlock)
+        .map(|quiz| quiz.id)
+        .collect();
+    let answers =
+        QuizUserAnswer::find_all_by_quizzes_and_user(conn, &writing_quiz_ids, submission.user_id)?;
+    Ok(answers.iter().any(|answer| answer.manual_score.is_none()))
+}
+
+// Assignment settings with the accommodation of the student applied
+pub fn get_student_assignment(
+    conn: &mut PgConnection,
+    assignment: Assignment,
+    user_id: i32,
+) -> Result<Assignment, IkigaiError> {
+    match AssignmentAccommodation::find_opt(conn, assignment.id, user_id)? {
+        Some(accommodation) => Ok(assignment.with_accommodation(&accommodation)),
+        None => Ok(assignment),
+    }
+}
+
+// Close submissions of the student at their own close time. The assignment wide close job
+// skips students with a different close time, so it's scheduled whenever an accommodation
+// is changed. Close time in the past is closed right away.
+pub fn schedule_close_student_assignment(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+    user_id: i32,
+) -> Result<(), IkigaiError> {
+    let student_assignment = get_student_assignment(conn, assignment.clone(), user_id)?;
+    if let Some(close_at) = student_assignment.close_at {
+        schedule_close_assignment(assignment.id, close_at, Some(user_id))?;
+    }
+
+    Ok(())
+}
+
+// Close submissions of the assignment at close time, or only the ones of a student
+// in case the student has their own close time. A job scheduled before for the same
+// assignment and student is replaced.
+pub fn schedule_close_assignment(
+    assignment_id: i32,
+    close_at: i64,
+    user_id: Option<i32>,
+) -> Result<(), IkigaiError> {
+    let message = CloseAssignment {
+        assignment_id,
+        close_at,
+        user_id,
+    };
+    let job = JobBuilder::default()
+        .message(message)
+        .id(get_close_assignment_job_id(assignment_id, user_id))
+        .job_type(JobType::ScheduledAt(get_date_from_ts(close_at)))
+        .build()?;
+    AJ::add_job(job);
+
+    Ok(())
+}
+
+pub fn cancel_close_assignment(assignment_id: i32, user_id: Option<i32>) {
+    AJ::cancel_job::<CloseAssignment>(get_close_assignment_job_id(assignment_id, user_id));
+}
+
+fn get_close_assignment_job_id(assignment_id: i32, user_id: Option<i32>) -> String {
+    match user_id {
+        Some(user_id) => format!("close_assignment_{}_{}", assignment_id, user_id),
+        None => format!("close_assignment_{}", assignment_id),
+    }
+}
+
+pub fn add_regrade_quiz_job(regrade: &AssignmentRegrade) -> Result<(), IkigaiError> {
+    let job = JobBuilder::default()
+        .message(RegradeQuiz {
+            regrade_id: regrade.id,
+        })
+        .id(format!("regrade_quiz_{}", regrade.id))
+        .build()?;
+    AJ::add_job(job);
+
+    Ok(())
+}
+
 pub fn try_add_rubric_submission(
     conn: &mut PgConnection,
     assignment: &Assignment,
@@ -46,7 +396,7 @@ pub fn try_add_rubric_submission(
     if let Some(rubric_id) = assignment.grade_by_rubric_id {
         if assignment.grade_method == GradeMethod::Rubric {
             let rubric = Rubric::find_by_id(conn, rubric_id)?;
-            let rubric_submission = RubricSubmission::new(submission.id, rubric.id, rubric.data);
+            let rubric_submission = RubricSubmission::new(submission.id, rubric);
             RubricSubmission::upsert(conn, rubric_submission)?;
         }
     }
@@ -57,7 +407,87 @@ pub fn auto_grade(
     conn: &mut PgConnection,
     document_id: Uuid,
     user_id: i32,
+    negative_marking: Option<f64>,
+) -> Result<f64, IkigaiError> {
+    let quizzes = find_document_quizzes(conn, document_id)?;
+    let quiz_ids = quizzes.iter().map(|quiz| quiz.id).collect();
+
+    let user_answers = QuizUserAnswer::find_all_by_quizzes_and_user(conn, &quiz_ids, user_id)?;
+    Ok(grade_answers(&quizzes, &user_answers, negative_marking))
+}
+
+// Negative marking is deducted from the total, so incorrect picks lower the grade
+// of the submission, which never goes below 0.
+pub fn grade_answers(
+    quizzes: &[Quiz],
+    answers: &[QuizUserAnswer],
+    negative_marking: Option<f64>,
+) -> f64 {
+    let total = answers
+        .iter()
+        .filter_map(|answer| {
+            let quiz = quizzes.iter().find(|quiz| quiz.id == answer.quiz_id)?;
+            let penalty = negative_marking
+                .map(|negative_marking| quiz.negative_marking_penalty(answer, negative_marking))
+                .unwrap_or_default();
+            Some(quiz.weighted_score(answer) - penalty)
+        })
+        .sum::<f64>();
+    total.max(0.0)
+}
+
+pub fn max_score(quizzes: &[Quiz]) -> f64 {
+    quizzes.iter().map(|quiz| quiz.points).sum()
+}
+
+pub fn get_assignment_max_score(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
 ) -> Result<f64, IkigaiError> {
+    if let Some(max_score) = assignment.max_score {
+        return Ok(max_score);
+    }
+
+    // Assignment created before we persist max score
+    let assignment = refresh_assignment_max_score(conn, assignment.document_id)?;
+    Ok(assignment
+        .and_then(|assignment| assignment.max_score)
+        .unwrap_or_default())
+}
+
+// Students may get different quizzes when the assignment draws them from question pools
+pub fn get_submission_max_score(
+    conn: &mut PgConnection,
+    assignment: &Assignment,
+    submission: &Submission,
+) -> Result<f64, IkigaiError> {
+    if assignment.question_pools.is_empty() {
+        return get_assignment_max_score(conn, assignment);
+    }
+
+    let quizzes = find_document_quizzes(conn, submission.document_id)?;
+    Ok(max_score(&quizzes))
+}
+
+pub fn refresh_assignment_max_score(
+    conn: &mut PgConnection,
+    document_id: Uuid,
+) -> Result<Option<Assignment>, IkigaiError> {
+    if let Some(assignment) = Assignment::find_by_document(conn, document_id)? {
+        let quizzes = find_document_quizzes(conn, document_id)?;
+        let max_score = assignment.question_pools.max_score(&quizzes);
+        let assignment = Assignment::update_max_score(conn, assignment.id, max_score)?;
+        return Ok(Some(assignment));
+    }
+
+    Ok(None)
+}
+
+// Quizzes which are still placed in the page contents of the document
+pub fn find_document_quizzes(
+    conn: &mut PgConnection,
+    document_id: Uuid,
+) -> Result<Vec<Quiz>, IkigaiError> {
     let pages = Page::find_all_by_document_id(conn, document_id)?;
     let page_ids = pages.iter().map(|page| page.id).collect();
 
@@ -66,12 +496,16 @@ pub fn auto_grade(
         .iter()
         .map(|page_content| page_content.id)
         .collect();
+    let quiz_block_ids: Vec<Uuid> = page_contents
+        .iter()
+        .flat_map(|page_content| page_content.get_json_content().find_quiz_block_ids())
+        .collect();
 
     let quizzes = Quiz::find_all_by_page_contents(conn, &page_content_ids)?;
-    let quiz_ids = quizzes.iter().map(|quiz| quiz.id).collect();
-
-    let user_answers = QuizUserAnswer::find_all_by_quizzes_and_user(conn, &quiz_ids, user_id)?;
-    Ok(user_answers.iter().map(|answer| answer.score).sum())
+    Ok(quizzes
+        .into_iter()
+        .filter(|quiz| quiz_block_ids.contains(&quiz.id))
+        .collect())
 }
 
 pub fn start_submission(
@@ -83,6 +517,7 @@ pub fn start_submission(
 ) -> Result<Submission, IkigaiError> {
     let user_id = user.id;
     let assignment_id = assignment.id;
+    let random_seed = rand::random::<i64>();
     let submission = conn.transaction::<_, IkigaiError, _>(|conn| {
         let config = DocumentCloneConfigBuilder::default()
             .prefix_title(format!("[{}] ", user.name()))
@@ -90,6 +525,8 @@ pub fn start_submission(
             .clone_to_space(assignment_document.space_id)
             .clone_children(false)
             .keep_document_type(false)
+            .excluded_quiz_ids(assignment.question_pools.excluded_quiz_ids(random_seed))
+            .shuffle_options_seed(assignment.shuffle_options.then_some(random_seed))
             .build()
             .unwrap();
         let document = assignment_document.deep_clone(conn, config)?;
@@ -103,6 +540,7 @@ pub fn start_submission(
                 .map_or_else(|| 1, |s| s.attempt_number + 1),
             assignment.test_duration.is_none(),
             assignment.test_duration,
+            Some(random_seed),
         );
         let submission = Submission::insert(conn, new_submission)?;
 
@@ -111,19 +549,7 @@ pub fn start_submission(
         Ok(submission)
     })?;
 
-    if let Some(close_in) = submission.test_duration {
-        let message = CompleteSubmission {
-            attempt_number: submission.attempt_number,
-            submission_id: submission.id,
-        };
-        let job = JobBuilder::default()
-            .message(message)
-            .job_type(JobType::ScheduledAt(get_date_from_ts(
-                get_now_as_secs() + close_in as i64,
-            )))
-            .build()?;
-        AJ::add_job(job);
-    }
+    schedule_complete_submission(&submission)?;
 
     let receiver_ids = vec![assignment_document.creator_id];
     let notification = Notification::new_do_assignment_notification(DoAssignmentContext {
@@ -137,3 +563,72 @@ pub fn start_submission(
 
     Ok(submission)
 }
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+    use serde_json::json;
+
+    use super::*;
+
+    fn choice_quiz(expected_choice: Uuid, points: f64) -> Quiz {
+        QuizBuilder::default()
+            .page_content_id(Uuid::new_v4())
+            .creator_id(1)
+            .quiz_type(QuizType::SingleChoice)
+            .question_data(json!({}))
+            .answer_data(json!({ "expectedChoices": [expected_choice] }))
+            .points(points)
+            .build()
+            .unwrap()
+    }
+
+    fn choice_answer(quiz: &Quiz, choice: Uuid) -> QuizUserAnswer {
+        let answer_data = json!({ "choices": [choice] });
+        QuizUserAnswer {
+            quiz_id: quiz.id,
+            user_id: 1,
+            score: try_get_auto_score(
+                quiz.quiz_type,
+                quiz.answer_data.clone(),
+                answer_data.clone(),
+            ),
+            answer_data,
+            updated_at: 0,
+            created_at: 0,
+            manual_score: None,
+            comment: None,
+            graded_at: None,
+        }
+    }
+
+    #[test]
+    fn grade_answers_deducts_negative_marking() {
+        let (correct, wrong) = (Uuid::new_v4(), Uuid::new_v4());
+        let quizzes = vec![choice_quiz(correct, 2.0), choice_quiz(correct, 2.0)];
+        let answers = vec![
+            choice_answer(&quizzes[0], correct),
+            choice_answer(&quizzes[1], wrong),
+        ];
+
+        assert_eq!(grade_answers(&quizzes, &answers, None), 2.0);
+        assert_eq!(grade_answers(&quizzes, &answers, Some(0.25)), 1.5);
+        assert_eq!(grade_answers(&quizzes, &answers, Some(1.0)), 0.0);
+    }
+
+    #[test]
+    fn grade_answers_never_goes_below_zero() {
+        let (correct, wrong) = (Uuid::new_v4(), Uuid::new_v4());
+        let quizzes = vec![choice_quiz(correct, 1.0), choice_quiz(correct, 3.0)];
+        let mut answers = vec![
+            choice_answer(&quizzes[0], wrong),
+            choice_answer(&quizzes[1], wrong),
+        ];
+        assert_eq!(grade_answers(&quizzes, &answers, Some(0.5)), 0.0);
+
+        // Teacher's score replaces the penalty of the answer
+        answers[1].manual_score = Some(3.0);
+        assert_eq!(grade_answers(&quizzes, &answers, Some(0.5)), 2.5);
+    }
+}
@@ -25,6 +25,26 @@ pub fn send_notification(
     Ok(())
 }
 
+// Send many notifications, each one to its own receivers
+pub fn send_notifications(
+    conn: &mut PgConnection,
+    notifications: Vec<(Notification, Vec<i32>)>,
+) -> Result<(), IkigaiError> {
+    let receivers = notifications
+        .iter()
+        .flat_map(|(notification, receiver_ids)| {
+            receiver_ids
+                .iter()
+                .map(move |receiver_id| NotificationReceiver::new(notification.id, *receiver_id))
+        })
+        .collect();
+    NotificationReceiver::upsert(conn, receivers)?;
+    for (notification, _) in notifications {
+        NotificationCenter::from_registry().do_send(SendNotification { notification });
+    }
+    Ok(())
+}
+
 pub fn parse_context(notification: &Notification) -> Option<Box<dyn ContextMessage>> {
     match notification.notification_type {
         NotificationType::NewSpaceMember => {
@@ -1,6 +1,6 @@
 use reqwest::Client;
 
-use crate::db::QuizType;
+use crate::db::{QuizType, RubricTableData, RubricType};
 use crate::error::IkigaiError;
 
 pub struct IkigaiAI;
@@ -91,6 +91,63 @@ pub struct AIGenerateQuizResponse {
     pub select_options_data: Option<AISelectOptionsResponseData>,
 }
 
+// Rubric Grading
+#[derive(Debug, Clone, Serialize)]
+pub struct GradeByRubricRequestData {
+    pub question: String,
+    pub answer: String,
+    pub rubric: RubricTableData,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AIRubricPick {
+    pub criteria_index: usize,
+    pub level_index: usize,
+    pub score: f64,
+    #[serde(default)]
+    pub comment: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AIGradeByRubricResponse {
+    pub picks: Vec<AIRubricPick>,
+}
+
+impl AIGradeByRubricResponse {
+    // Apply picks to the rubric, picks out of the rubric are skipped and scores are kept
+    // in the range of the picked level.
+    pub fn to_graded_data(&self, rubric: &RubricTableData) -> RubricTableData {
+        let mut graded_data = rubric.clone();
+        for item in graded_data.items.iter_mut().flatten() {
+            item.user_pick.selected = false;
+        }
+
+        for pick in self.picks.iter() {
+            let Some(inner_items) = graded_data.items.get_mut(pick.criteria_index) else {
+                continue;
+            };
+            if inner_items.iter().any(|item| item.user_pick.selected) {
+                continue;
+            }
+            let Some(item) = inner_items.get_mut(pick.level_index) else {
+                continue;
+            };
+
+            item.user_pick.selected = true;
+            item.user_pick.score = match graded_data.rubric_type {
+                RubricType::PointBased => item.score,
+                RubricType::PointRange if pick.score.is_finite() => {
+                    pick.score.clamp(item.score, item.to_score.max(item.score))
+                }
+                RubricType::PointRange => item.score,
+            };
+            item.user_pick.comment.clone_from(&pick.comment);
+        }
+
+        graded_data
+    }
+}
+
 impl IkigaiAI {
     fn get_url() -> String {
         std::env::var("IKIGAI_AI_HOST").unwrap_or("http://localhost:8001".into())
@@ -144,6 +201,22 @@ impl IkigaiAI {
         Ok(res)
     }
 
+    pub async fn grade_by_rubric(
+        data: &GradeByRubricRequestData,
+    ) -> Result<AIGradeByRubricResponse, IkigaiError> {
+        let base_url = Self::get_url();
+        let url = format!("{base_url}/rubrics/grade-writing");
+        let res = Client::new()
+            .post(url)
+            .json(data)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        Ok(res)
+    }
+
     pub async fn generate_select_options(
         data: &GenerateQuizzesRequestData,
     ) -> Result<AIGenerateQuizResponse, IkigaiError> {
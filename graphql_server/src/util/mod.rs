@@ -11,6 +11,8 @@ pub mod log_util;
 pub mod markdown_util;
 pub mod url_util;
 pub mod var_util;
+pub mod xlsx_util;
+pub mod yjs_util;
 
 use crate::constant::{FIRST_MONDAY_TIMESTAMP, TOTAL_SECONDS_OF_A_WEEK};
 
@@ -0,0 +1,102 @@
+use rust_xlsxwriter::{ColNum, RowNum, Workbook};
+
+use crate::error::IkigaiError;
+
+pub enum XlsxCell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+// Single sheet workbook, numbers which Excel can't store (NaN, infinity) are left empty
+pub fn write_xlsx(sheet_name: &str, rows: &[Vec<XlsxCell>]) -> Result<Vec<u8>, IkigaiError> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(sheet_name)?;
+
+    for (row_index, row) in rows.iter().enumerate() {
+        for (col_index, cell) in row.iter().enumerate() {
+            let (row_num, col_num) = (row_index as RowNum, col_index as ColNum);
+            match cell {
+                XlsxCell::Text(text) => {
+                    worksheet.write_string(row_num, col_num, text)?;
+                }
+                XlsxCell::Number(number) if number.is_finite() => {
+                    worksheet.write_number(row_num, col_num, *number)?;
+                }
+                XlsxCell::Number(_) | XlsxCell::Empty => {}
+            }
+        }
+    }
+
+    Ok(workbook.save_to_buffer()?)
+}
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+    use std::io::{Cursor, Read};
+
+    use super::*;
+
+    fn read_file(data: &[u8], name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn write_xlsx_round_trip() {
+        let rows = vec![
+            vec![
+                XlsxCell::Text("Name".to_string()),
+                XlsxCell::Text("Grade".to_string()),
+            ],
+            vec![
+                XlsxCell::Text("Nguyễn <Văn> & \"A\"".to_string()),
+                XlsxCell::Number(8.5),
+            ],
+            vec![XlsxCell::Text("=1+1".to_string()), XlsxCell::Empty],
+        ];
+        let data = write_xlsx("Gradebook", &rows).unwrap();
+
+        let workbook = read_file(&data, "xl/workbook.xml");
+        assert!(workbook.contains(r#"name="Gradebook""#));
+
+        let shared_strings = read_file(&data, "xl/sharedStrings.xml");
+        assert!(shared_strings.contains("<t>Nguyễn &lt;Văn&gt; &amp; \"A\"</t>"));
+        assert!(shared_strings.contains("<t>=1+1</t>"));
+
+        let sheet = read_file(&data, "xl/worksheets/sheet1.xml");
+        assert!(sheet.contains(r#"<c r="B2"><v>8.5</v></c>"#));
+        assert!(!sheet.contains(r#"r="B3""#));
+        assert!(!sheet.contains("<f>"));
+    }
+
+    #[test]
+    fn write_xlsx_skips_invalid_numbers() {
+        let rows = vec![vec![
+            XlsxCell::Number(f64::NAN),
+            XlsxCell::Number(f64::INFINITY),
+            XlsxCell::Number(-1.0),
+        ]];
+        let data = write_xlsx("Sheet", &rows).unwrap();
+
+        let sheet = read_file(&data, "xl/worksheets/sheet1.xml");
+        assert!(!sheet.contains("NaN"));
+        assert!(!sheet.contains("inf"));
+        assert!(!sheet.contains(r#"r="A1""#));
+        assert!(sheet.contains(r#"<c r="C1"><v>-1</v></c>"#));
+    }
+
+    #[test]
+    fn write_xlsx_rejects_invalid_sheet_name() {
+        assert!(write_xlsx("Grades [2024]", &[]).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use serde_json::{Map, Value};
+use yrs::types::text::YChange;
+use yrs::types::Attrs;
+use yrs::updates::decoder::Decode;
+use yrs::{
+    Any, Doc, Out, ReadTxn, StateVector, Text, Transact, TransactionMut, Update, Xml,
+    XmlElementPrelim, XmlFragment, XmlOut, XmlTextPrelim,
+};
+
+use crate::error::IkigaiError;
+
+// Name of the shared XML fragment which is bound to the editor by the tiptap Collaboration
+// extension. Nodes are XML elements named by their type and text nodes are XML texts
+// formatted by their marks, the same as y-prosemirror.
+pub const YJS_FRAGMENT_NAME: &str = "default";
+
+// Yjs document with the content of a tiptap body
+pub fn new_yjs_doc(body: &Value) -> Doc {
+    let doc = Doc::new();
+    let fragment = doc.get_or_insert_xml_fragment(YJS_FRAGMENT_NAME);
+    if let Some(nodes) = body.get("content").and_then(|content| content.as_array()) {
+        let mut txn = doc.transact_mut();
+        push_json_nodes(&mut txn, &fragment, nodes);
+    }
+    doc
+}
+
+pub fn apply_yjs_update(doc: &Doc, update: &[u8]) -> Result<(), IkigaiError> {
+    let update = Update::decode_v1(update)
+        .map_err(|_| IkigaiError::new_bad_request("Collaboration update is not a Yjs update"))?;
+    doc.transact_mut()
+        .apply_update(update)
+        .map_err(|_| IkigaiError::new_bad_request("Cannot apply the collaboration update"))
+}
+
+// Full state of the document as a single Yjs update
+pub fn encode_yjs_state(doc: &Doc) -> Vec<u8> {
+    doc.transact()
+        .encode_state_as_update_v1(&StateVector::default())
+}
+
+// Tiptap body rendered from the document
+pub fn render_yjs_body(doc: &Doc) -> Value {
+    let fragment = doc.get_or_insert_xml_fragment(YJS_FRAGMENT_NAME);
+    let txn = doc.transact();
+    let mut body = Map::new();
+    body.insert("type".into(), "doc".into());
+    body.insert("content".into(), render_xml_nodes(&txn, &fragment).into());
+    body.into()
+}
+
+fn push_json_nodes<F: XmlFragment>(txn: &mut TransactionMut, parent: &F, nodes: &[Value]) {
+    let mut index = 0;
+    while index < nodes.len() {
+        let node = &nodes[index];
+        if node.get("type").and_then(|node_type| node_type.as_str()) == Some("text") {
+            // Adjacent text nodes are chunks of a single XML text
+            let text = parent.push_back(txn, XmlTextPrelim::new(""));
+            while let Some(node) = nodes
+                .get(index)
+                .filter(|node| node.get("type").and_then(|t| t.as_str()) == Some("text"))
+            {
+                let chunk = node
+                    .get("text")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or_default();
+                let position = text.len(txn);
+                text.insert_with_attributes(txn, position, chunk, get_mark_attrs(node));
+                index += 1;
+            }
+            continue;
+        }
+
+        let tag = node
+            .get("type")
+            .and_then(|node_type| node_type.as_str())
+            .unwrap_or("paragraph");
+        let element = parent.push_back(txn, XmlElementPrelim::empty(tag));
+        if let Some(attrs) = node.get("attrs").and_then(|attrs| attrs.as_object()) {
+            for (name, value) in attrs {
+                match value {
+                    Value::Null => {}
+                    Value::String(value) => element.insert_attribute(txn, name.as_str(), value),
+                    value => element.insert_attribute(txn, name.as_str(), value.to_string()),
+                }
+            }
+        }
+        if let Some(children) = node.get("content").and_then(|content| content.as_array()) {
+            push_json_nodes(txn, &element, children);
+        }
+        index += 1;
+    }
+}
+
+fn get_mark_attrs(node: &Value) -> Attrs {
+    let mut attrs = Attrs::new();
+    for mark in node
+        .get("marks")
+        .and_then(|marks| marks.as_array())
+        .into_iter()
+        .flatten()
+    {
+        if let Some(mark_type) = mark.get("type").and_then(|mark_type| mark_type.as_str()) {
+            let value = mark
+                .get("attrs")
+                .and_then(|attrs| Any::from_json(&attrs.to_string()).ok())
+                .unwrap_or_else(|| Any::Map(Default::default()));
+            attrs.insert(mark_type.into(), value);
+        }
+    }
+    attrs
+}
+
+fn render_xml_nodes<F: XmlFragment, T: ReadTxn>(txn: &T, parent: &F) -> Vec<Value> {
+    let mut nodes = vec![];
+    for child in parent.children(txn) {
+        match child {
+            XmlOut::Element(element) => {
+                let mut node = Map::new();
+                node.insert("type".into(), element.tag().to_string().into());
+                // Attributes are stored as strings, values of other types are in JSON
+                let attrs: Map<String, Value> = element
+                    .attributes(txn)
+                    .map(|(name, value)| {
+                        let value = match serde_json::from_str::<Value>(&value) {
+                            Ok(Value::String(_)) | Err(_) => Value::String(value),
+                            Ok(value) => value,
+                        };
+                        (name.to_string(), value)
+                    })
+                    .collect();
+                if !attrs.is_empty() {
+                    node.insert("attrs".into(), attrs.into());
+                }
+                let content = render_xml_nodes(txn, &element);
+                if !content.is_empty() {
+                    node.insert("content".into(), content.into());
+                }
+                nodes.push(node.into());
+            }
+            XmlOut::Text(text) => {
+                for diff in text.diff(txn, YChange::identity) {
+                    let chunk = match diff.insert {
+                        Out::Any(Any::String(chunk)) => chunk,
+                        _ => continue,
+                    };
+                    let mut node = Map::new();
+                    node.insert("type".into(), "text".into());
+                    node.insert("text".into(), chunk.to_string().into());
+                    let marks = render_marks(diff.attributes.as_deref());
+                    if !marks.is_empty() {
+                        node.insert("marks".into(), marks.into());
+                    }
+                    nodes.push(node.into());
+                }
+            }
+            XmlOut::Fragment(fragment) => nodes.extend(render_xml_nodes(txn, &fragment)),
+        }
+    }
+    nodes
+}
+
+fn render_marks(attrs: Option<&Attrs>) -> Vec<Value> {
+    let mut marks: Vec<Value> = attrs
+        .into_iter()
+        .flatten()
+        .map(|(mark_type, value)| {
+            let mut mark = Map::new();
+            mark.insert("type".into(), mark_type.to_string().into());
+            let mut json = String::new();
+            value.to_json(&mut json);
+            match serde_json::from_str::<Value>(&json) {
+                Ok(Value::Object(attrs)) if attrs.is_empty() => {}
+                Ok(Value::Null) | Err(_) => {}
+                Ok(attrs) => {
+                    mark.insert("attrs".into(), attrs);
+                }
+            }
+            mark.into()
+        })
+        .collect();
+    // Marks are kept in a map, sort them so the rendered body is stable
+    marks.sort_by_key(|mark| mark.get("type").map(|mark_type| mark_type.to_string()));
+    marks
+}
+
+#[cfg(test)]
+mod tests {
+    // `test` of actix is in scope by macro_use
+    use core::prelude::v1::test;
+    use serde_json::json;
+    use yrs::GetString;
+
+    use super::*;
+
+    fn sample_body() -> Value {
+        json!({
+            "type": "doc",
+            "content": [
+                {
+                    "type": "heading",
+                    "attrs": { "level": 2 },
+                    "content": [{ "type": "text", "text": "Unit 1" }]
+                },
+                {
+                    "type": "paragraph",
+                    "content": [
+                        { "type": "text", "text": "Read " },
+                        {
+                            "type": "text",
+                            "text": "the article",
+                            "marks": [
+                                { "type": "bold" },
+                                { "type": "link", "attrs": { "href": "https://ikigai.li" } }
+                            ]
+                        }
+                    ]
+                },
+                {
+                    "type": "quizBlock",
+                    "attrs": { "quizId": "3f1d6a4e-2f7a-4d8e-9a57-0c1e9b6d0a11", "readOnly": false }
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn yjs_doc_round_trip() {
+        let body = sample_body();
+        let doc = new_yjs_doc(&body);
+        assert_eq!(render_yjs_body(&doc), body);
+    }
+
+    #[test]
+    fn yjs_doc_of_empty_body() {
+        let doc = new_yjs_doc(&json!({}));
+        assert_eq!(
+            render_yjs_body(&doc),
+            json!({ "type": "doc", "content": [] })
+        );
+    }
+
+    #[test]
+    fn yjs_state_is_shared_by_clients() {
+        let server = new_yjs_doc(&sample_body());
+        let state = encode_yjs_state(&server);
+
+        // Both clients start from the state of the server and edit the same paragraph
+        let (first, second) = (Doc::new(), Doc::new());
+        for client in [&first, &second] {
+            apply_yjs_update(client, &state).unwrap();
+        }
+        let edit = |client: &Doc, chunk: &str| {
+            let fragment = client.get_or_insert_xml_fragment(YJS_FRAGMENT_NAME);
+            let mut txn = client.transact_mut();
+            let paragraph = match fragment.get(&txn, 1) {
+                Some(XmlOut::Element(paragraph)) => paragraph,
+                _ => panic!("Paragraph is missing"),
+            };
+            let text = match paragraph.get(&txn, 0) {
+                Some(XmlOut::Text(text)) => text,
+                _ => panic!("Text is missing"),
+            };
+            text.insert(&mut txn, 0, chunk);
+            txn.encode_update_v1()
+        };
+        let first_update = edit(&first, "Please ");
+        let second_update = edit(&second, "Now ");
+
+        // Updates are merged in any order and more than once
+        apply_yjs_update(&server, &second_update).unwrap();
+        apply_yjs_update(&server, &first_update).unwrap();
+        apply_yjs_update(&server, &first_update).unwrap();
+
+        let body = render_yjs_body(&server);
+        let paragraph = &body["content"][1]["content"];
+        let text = format!(
+            "{}{}",
+            paragraph[0]["text"].as_str().unwrap(),
+            paragraph[1]["text"].as_str().unwrap()
+        );
+        assert!(
+            text == "Please Now Read the article" || text == "Now Please Read the article",
+            "{}",
+            text
+        );
+        assert_eq!(body["content"][0], sample_body()["content"][0]);
+
+        let fragment = server.get_or_insert_xml_fragment(YJS_FRAGMENT_NAME);
+        assert_eq!(fragment.len(&server.transact()), 3);
+        assert!(fragment.get_string(&server.transact()).contains("Unit 1"));
+    }
+
+    #[test]
+    fn apply_invalid_yjs_update() {
+        let doc = new_yjs_doc(&sample_body());
+        assert!(apply_yjs_update(&doc, &[255, 255, 255]).is_err());
+        assert_eq!(render_yjs_body(&doc), sample_body());
+    }
+}
@@ -27,6 +27,11 @@ from ikigai_ai.src.quiz_generator.select_option import (
     generate_select_options_quizzes,
     SelectOptionList,
 )
+from ikigai_ai.src.rubric_grader.writing import (
+    grade_writing_by_rubric,
+    GradeWritingRequest,
+    RubricPickList,
+)
 
 app = FastAPI()
 
@@ -103,3 +108,12 @@ def gen_single_choice_quizzes(req: GenerateQuizRequest):
     return GenerateQuizResponse(
         quiz_type=QuizType.FillInBlank, select_options_data=select_options_data
     )
+
+
+@app.post("/rubrics/grade-writing")
+def grade_writing(req: GradeWritingRequest) -> RubricPickList:
+    return grade_writing_by_rubric(
+        req.question,
+        req.answer,
+        req.rubric,
+    )
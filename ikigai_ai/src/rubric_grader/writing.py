@@ -0,0 +1,97 @@
+from typing import List
+
+from pydantic import BaseModel
+from llama_index.llms.openai import OpenAI
+from llama_index.program.openai import OpenAIPydanticProgram
+
+
+class RubricUserPick(BaseModel):
+    selected: bool
+    score: float
+    comment: str
+
+
+class RubricTableItem(BaseModel):
+    explanation: str
+    score: float
+    to_score: float = 0
+    user_pick: RubricUserPick | None = None
+
+
+class RubricTableData(BaseModel):
+    rubric_type: str = "PointBased"
+    criteria: List[str]
+    weighting_criteria: List[float | None] = []
+    level: List[str]
+    items: List[List[RubricTableItem]]
+
+
+class GradeWritingRequest(BaseModel):
+    question: str
+    answer: str
+    rubric: RubricTableData
+
+
+class RubricPick(BaseModel):
+    """Data Model for the picked level of a rubric criterion"""
+
+    criteria_index: int
+    level_index: int
+    score: float
+    comment: str
+
+
+class RubricPickList(BaseModel):
+    """Data model for picked levels of a rubric, one pick per criterion."""
+
+    picks: List[RubricPick]
+
+
+llm = OpenAI(model="gpt-4o-mini")
+
+
+def format_rubric(rubric: RubricTableData) -> str:
+    lines = []
+    for criteria_index, criteria in enumerate(rubric.criteria):
+        lines.append(f"Criterion {criteria_index}: {criteria}")
+        for level_index, item in enumerate(rubric.items[criteria_index]):
+            level = rubric.level[level_index] if level_index < len(rubric.level) else ""
+            if rubric.rubric_type == "PointRange":
+                score = f"{item.score} - {item.to_score} points"
+            else:
+                score = f"{item.score} points"
+            lines.append(
+                f"  Level {level_index} ({level}, {score}): {item.explanation}"
+            )
+    return "\n".join(lines)
+
+
+# Question, answer and rubric are template variables, so braces in them
+# (code, math, JSON) are kept as they are. Literal braces in this template must be doubled.
+GRADE_WRITING_PROMPT = """
+    Question:\n {question}
+    Student answer:\n {answer}
+    Rubric:\n {rubric}
+
+    Grade the student answer by the rubric. Pick exactly one level for each criterion,
+    give a score within the points of the picked level and a short comment for the student.
+    """
+
+
+def grade_writing_by_rubric(
+    question: str,
+    answer: str,
+    rubric: RubricTableData,
+) -> RubricPickList:
+    program = OpenAIPydanticProgram.from_defaults(
+        output_cls=RubricPickList,
+        prompt_template_str=GRADE_WRITING_PROMPT,
+        verbose=True,
+    )
+
+    output = program(
+        question=question,
+        answer=answer,
+        rubric=format_rubric(rubric),
+    )
+    return output
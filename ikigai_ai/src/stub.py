@@ -0,0 +1,32 @@
+# Stub of the AI service for local development and testing of graphql_server.
+# It doesn't call any LLM and returns deterministic responses.
+from fastapi import FastAPI
+
+from ikigai_ai.src.rubric_grader.writing import (
+    GradeWritingRequest,
+    RubricPick,
+    RubricPickList,
+)
+
+app = FastAPI()
+
+
+@app.get("/ping")
+def read_root():
+    return "pong"
+
+
+@app.post("/rubrics/grade-writing")
+def grade_writing(req: GradeWritingRequest) -> RubricPickList:
+    # Pick the first level of each criterion
+    picks = [
+        RubricPick(
+            criteria_index=criteria_index,
+            level_index=0,
+            score=items[0].score,
+            comment=f"Stub feedback for {req.rubric.criteria[criteria_index]}",
+        )
+        for criteria_index, items in enumerate(req.rubric.items)
+        if items
+    ]
+    return RubricPickList(picks=picks)
@@ -0,0 +1,48 @@
+import unittest
+from unittest.mock import patch
+
+from llama_index.core.prompts import PromptTemplate
+
+from ikigai_ai.src.rubric_grader.writing import (
+    RubricPickList,
+    RubricTableData,
+    RubricTableItem,
+    grade_writing_by_rubric,
+)
+
+
+class GradeWritingByRubricTest(unittest.TestCase):
+    @patch("ikigai_ai.src.rubric_grader.writing.OpenAIPydanticProgram")
+    def test_braces_in_answer_and_rubric(self, program_class):
+        prompts = []
+
+        def run_program(**kwargs):
+            template = program_class.from_defaults.call_args.kwargs[
+                "prompt_template_str"
+            ]
+            prompts.append(PromptTemplate(template).format(**kwargs))
+            return RubricPickList(picks=[])
+
+        program_class.from_defaults.return_value.side_effect = run_program
+
+        question = "Describe the set {x | x > 0}"
+        answer = 'It is like the JSON {"positive": true} and {0} is not in it'
+        rubric = RubricTableData(
+            criteria=["Uses notation like {a, b}"],
+            level=["Good"],
+            items=[
+                [RubricTableItem(explanation="Writes {} for the empty set", score=2)]
+            ],
+        )
+        output = grade_writing_by_rubric(question, answer, rubric)
+
+        self.assertEqual(output, RubricPickList(picks=[]))
+        self.assertEqual(len(prompts), 1)
+        self.assertIn(question, prompts[0])
+        self.assertIn(answer, prompts[0])
+        self.assertIn("Criterion 0: Uses notation like {a, b}", prompts[0])
+        self.assertIn("Writes {} for the empty set", prompts[0])
+
+
+if __name__ == "__main__":
+    unittest.main()